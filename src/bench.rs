@@ -0,0 +1,128 @@
+//! `--bench`: how many RPC round-trips an acceleration really takes. Every call
+//! a demo makes goes through [`MeteredClient`], which times it when
+//! benchmarking, so redundant calls (the same snapshot fetched twice) show up.
+//! With `--emit-script` it also records the calls worth replaying by hand, and
+//! it notes every transaction a call broadcasts for `--reset`.
+
+use bitcoin::Txid;
+use bitcoincore_rpc::{Client, RpcApi};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::cli_script::CliCommand;
+
+/// RPCs that put a new transaction in the mempool.
+const BROADCASTING: [&str; 6] = ["sendrawtransaction", "submitpackage", "bumpfee", "sendtoaddress", "sendmany", "send"];
+
+/// The wallet RPC client, optionally recording every call.
+pub struct MeteredClient {
+    client: Client,
+    /// The wallet `client` is bound to.
+    wallet: String,
+    /// Every transaction a call has put in the mempool, for `--reset`.
+    broadcasts: Mutex<Vec<Txid>>,
+    /// `Some` with `--bench`.
+    calls: Option<Mutex<Vec<(String, Duration)>>>,
+    /// `Some` with `--emit-script`.
+    script: Option<Mutex<Vec<CliCommand>>>,
+}
+
+impl MeteredClient {
+    pub fn new(client: Client, wallet: &str, bench: bool, emit_script: bool) -> Self {
+        MeteredClient {
+            client,
+            wallet: wallet.to_string(),
+            broadcasts: Mutex::new(Vec::new()),
+            calls: bench.then(|| Mutex::new(Vec::new())),
+            script: emit_script.then(|| Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Adds `step` to the emitted script, for calls made outside this client.
+    pub fn record(&self, step: CliCommand) {
+        if let Some(script) = &self.script {
+            script.lock().unwrap().push(step);
+        }
+    }
+
+    /// The commands recorded for `--emit-script` so far.
+    pub fn script(&self) -> Option<Vec<CliCommand>> {
+        Some(self.script.as_ref()?.lock().unwrap().clone())
+    }
+
+    /// The transactions broadcast through this client since the last call.
+    pub fn take_broadcasts(&self) -> Vec<Txid> {
+        std::mem::take(&mut *self.broadcasts.lock().unwrap())
+    }
+
+    /// Notes the txids a successful `cmd` returned, if it broadcasts.
+    fn note_broadcasts(&self, cmd: &str, result: &serde_json::Value) {
+        if !BROADCASTING.contains(&cmd) {
+            return;
+        }
+        // A bare txid, `{"txid": ...}`, or submitpackage's per-transaction results
+        let found: Vec<&serde_json::Value> = match result {
+            serde_json::Value::String(_) => vec![result],
+            _ if result.get("txid").is_some() => vec![&result["txid"]],
+            _ => result["tx-results"].as_object().map_or(Vec::new(), |txs| txs.values().map(|tx| &tx["txid"]).collect()),
+        };
+        let txids = found.into_iter().filter_map(|txid| txid.as_str()?.parse::<Txid>().ok());
+        self.broadcasts.lock().unwrap().extend(txids);
+    }
+
+    /// What was recorded so far, if benchmarking.
+    pub fn stats(&self) -> Option<RpcStats> {
+        let calls = self.calls.as_ref()?.lock().unwrap().clone();
+        Some(RpcStats { calls })
+    }
+}
+
+impl RpcApi for MeteredClient {
+    fn call<T: for<'a> Deserialize<'a>>(&self, cmd: &str, args: &[serde_json::Value]) -> bitcoincore_rpc::Result<T> {
+        if CliCommand::is_replayed(cmd) {
+            self.record(CliCommand { wallet: Some(self.wallet.clone()), method: cmd.to_string(), args: args.to_vec() });
+        }
+        let start = Instant::now();
+        let result = self.client.call::<serde_json::Value>(cmd, args);
+        if let Some(calls) = &self.calls {
+            calls.lock().unwrap().push((cmd.to_string(), start.elapsed()));
+        }
+        let result = result?;
+        self.note_broadcasts(cmd, &result);
+        Ok(serde_json::from_value(result)?)
+    }
+}
+
+/// Every RPC call of a run with how long it took, in order.
+pub struct RpcStats {
+    calls: Vec<(String, Duration)>,
+}
+
+impl fmt::Display for RpcStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total: Duration = self.calls.iter().map(|(_, took)| *took).sum();
+        writeln!(f, "RPC benchmark: {} calls, {:.1} ms total", self.calls.len(), total.as_secs_f64() * 1000.0)?;
+        if let Some((cmd, took)) = self.calls.iter().max_by_key(|(_, took)| *took) {
+            writeln!(f, "   ├─ Slowest: {} ({:.1} ms)", cmd, took.as_secs_f64() * 1000.0)?;
+        }
+        let mut per_cmd: HashMap<&str, (usize, Duration)> = HashMap::new();
+        for (cmd, took) in &self.calls {
+            let entry = per_cmd.entry(cmd).or_default();
+            entry.0 += 1;
+            entry.1 += *took;
+        }
+        let mut per_cmd: Vec<_> = per_cmd.into_iter().collect();
+        per_cmd.sort_by(|a, b| b.1.0.cmp(&a.1.0).then(a.0.cmp(b.0)));
+        for (i, (cmd, (count, took))) in per_cmd.iter().enumerate() {
+            let branch = if i + 1 == per_cmd.len() { "└─" } else { "├─" };
+            write!(f, "   {} {:<28} {:>4}× {:>8.1} ms", branch, cmd, count, took.as_secs_f64() * 1000.0)?;
+            if i + 1 < per_cmd.len() {
+                writeln!(f)?;
+            }
+        }
+        Ok(())
+    }
+}
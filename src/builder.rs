@@ -0,0 +1,147 @@
+//! The one transaction builder the RBF, CPFP and P2A demos share. Inputs and
+//! outputs go into the transaction exactly as given and it is serialized by
+//! hand, so it can express outputs `createrawtransaction` can't: zero-value
+//! outputs, P2A anchors, arbitrary scripts.
+
+use anyhow::{ensure, Result};
+use bitcoin::absolute::LockTime;
+use bitcoin::transaction::Version;
+use bitcoin::{Address, Amount, OutPoint, ScriptBuf, Sequence, Transaction, TxIn, TxOut, Witness};
+use bitcoincore_rpc::json::{SignRawTransactionInput, SignRawTransactionResult};
+use bitcoincore_rpc::RpcApi;
+
+use crate::node::Node;
+
+/// An unsigned transaction under construction.
+pub struct TxBuilder {
+    tx: Transaction,
+}
+
+impl TxBuilder {
+    pub fn new(version: Version, lock_time: LockTime) -> Self {
+        TxBuilder {
+            tx: Transaction { version, lock_time, input: Vec::new(), output: Vec::new() },
+        }
+    }
+
+    /// Spends `prevout` with `sequence`; script and witness are left for signing.
+    pub fn input(mut self, prevout: OutPoint, sequence: Sequence) -> Self {
+        self.tx.input.push(TxIn {
+            previous_output: prevout,
+            script_sig: ScriptBuf::new(),
+            sequence,
+            witness: Witness::new(),
+        });
+        self
+    }
+
+    /// Pays `value` to any script, including zero to an anchor.
+    pub fn output(mut self, value: Amount, script_pubkey: ScriptBuf) -> Self {
+        self.tx.output.push(TxOut { value, script_pubkey });
+        self
+    }
+
+    pub fn pay(self, value: Amount, address: &Address) -> Self {
+        self.output(value, address.script_pubkey())
+    }
+
+    /// Orders the outputs as BIP69 does: by value, then by scriptPubKey bytes.
+    pub fn sort_outputs_bip69(mut self) -> Self {
+        self.tx.output.sort_by(|a, b| a.value.cmp(&b.value).then_with(|| a.script_pubkey.as_bytes().cmp(b.script_pubkey.as_bytes())));
+        self
+    }
+
+    pub fn build(self) -> Transaction {
+        self.tx
+    }
+}
+
+/// Checks that signing only filled in scriptSigs and witnesses: same version,
+/// locktime, spent outpoints, sequences and outputs, in the same order. The
+/// txid can still differ (a scriptSig is part of it), so callers must take it
+/// from the signed transaction.
+pub fn ensure_same_structure(unsigned: &Transaction, signed: &Transaction) -> Result<()> {
+    ensure!(
+        signed.version == unsigned.version && signed.lock_time == unsigned.lock_time,
+        "signing changed the version or locktime of {}", unsigned.compute_txid()
+    );
+    let spends = |tx: &Transaction| tx.input.iter().map(|input| (input.previous_output, input.sequence)).collect::<Vec<_>>();
+    ensure!(spends(signed) == spends(unsigned), "signing changed the inputs of {}", unsigned.compute_txid());
+    ensure!(signed.output == unsigned.output, "signing changed the outputs of {}", unsigned.compute_txid());
+    Ok(())
+}
+
+/// `tx` with every scriptSig and witness cleared: the starting point for a
+/// replacement the wallet signs afresh.
+pub fn unsigned_copy(tx: &Transaction) -> Transaction {
+    let mut copy = tx.clone();
+    for input in &mut copy.input {
+        input.script_sig = ScriptBuf::new();
+        input.witness = Witness::new();
+    }
+    copy
+}
+
+impl Node {
+    /// A [`TxBuilder`] for a v2 transaction with this run's `nLockTime`.
+    pub fn tx_builder(&self) -> Result<TxBuilder> {
+        Ok(TxBuilder::new(Version::TWO, self.lock_time()?))
+    }
+
+    /// Signs every input of `tx` the wallet can, failing unless all were signed
+    /// and the signed transaction is still `tx` apart from its signatures.
+    pub fn sign_with_wallet(&self, tx: &Transaction) -> Result<SignRawTransactionResult> {
+        let signed = self.rpc.sign_raw_transaction_with_wallet(tx, None, None)?;
+        ensure!(signed.complete, "wallet could not fully sign {}: {:?}", tx.compute_txid(), signed.errors);
+        ensure_same_structure(tx, &signed.transaction()?)?;
+        Ok(signed)
+    }
+
+    /// [`Node::sign_with_wallet`] for a child of `parent` before `parent` is
+    /// broadcast: the outputs it spends are handed to the wallet directly.
+    pub fn sign_child_with_wallet(&self, tx: &Transaction, parent: &Transaction) -> Result<SignRawTransactionResult> {
+        let parent_txid = parent.compute_txid();
+        let prevtxs: Vec<SignRawTransactionInput> = tx
+            .input
+            .iter()
+            .filter(|input| input.previous_output.txid == parent_txid)
+            .map(|input| {
+                let output = &parent.output[input.previous_output.vout as usize];
+                SignRawTransactionInput {
+                    txid: parent_txid,
+                    vout: input.previous_output.vout,
+                    script_pub_key: output.script_pubkey.clone(),
+                    redeem_script: None,
+                    amount: Some(output.value),
+                }
+            })
+            .collect();
+        let signed = self.rpc.sign_raw_transaction_with_wallet(tx, Some(&prevtxs), None)?;
+        ensure!(signed.complete, "wallet could not fully sign {}: {:?}", tx.compute_txid(), signed.errors);
+        ensure_same_structure(tx, &signed.transaction()?)?;
+        Ok(signed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::consensus::encode::{deserialize, serialize};
+
+    use crate::consts::{SEQUENCE_FINAL, TRUC_VERSION};
+    use crate::p2a::p2a_script;
+
+    /// The builder expresses what `createrawtransaction` can't, a zero-value
+    /// P2A output, and it survives serialization unchanged.
+    #[test]
+    fn zero_value_output_round_trips() {
+        let tx = TxBuilder::new(TRUC_VERSION, LockTime::ZERO)
+            .input(OutPoint::null(), SEQUENCE_FINAL)
+            .output(Amount::ZERO, p2a_script())
+            .build();
+        let decoded: Transaction = deserialize(&serialize(&tx)).unwrap();
+        assert_eq!(decoded, tx);
+        assert_eq!(decoded.output[0].value, Amount::ZERO);
+        assert_eq!(decoded.output[0].script_pubkey, p2a_script());
+    }
+}
@@ -0,0 +1,118 @@
+//! Which optional RPCs and mempool policies the connected Bitcoin Core offers.
+
+use anyhow::{bail, Result};
+use bitcoincore_rpc::RpcApi;
+use std::fmt;
+
+use crate::node::Node;
+
+/// A Bitcoin Core release, decoded from `getnetworkinfo`'s integer `version`
+/// (e.g. `280100` → 28.1.0).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CoreVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl CoreVersion {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        CoreVersion { major, minor, patch }
+    }
+
+    pub fn from_network_info(version: usize) -> Self {
+        let version = version as u32;
+        CoreVersion::new(version / 10_000, version / 100 % 100, version % 100)
+    }
+}
+
+impl fmt::Display for CoreVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Optional RPCs and policies the demos rely on.
+#[derive(Debug, Clone, Copy)]
+pub enum Feature {
+    GenerateBlock,
+    MaxBurnAmount,
+    SubmitPackage,
+    Truc,
+    TxSpendingPrevout,
+}
+
+impl Feature {
+    pub const ALL: [Feature; 5] = [Feature::GenerateBlock, Feature::MaxBurnAmount, Feature::SubmitPackage, Feature::Truc, Feature::TxSpendingPrevout];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Feature::GenerateBlock => "generateblock",
+            Feature::MaxBurnAmount => "sendrawtransaction maxburnamount",
+            Feature::SubmitPackage => "submitpackage",
+            Feature::Truc => "TRUC (v3) / P2A policy",
+            Feature::TxSpendingPrevout => "gettxspendingprevout",
+        }
+    }
+
+    pub fn min_version(self) -> CoreVersion {
+        match self {
+            Feature::GenerateBlock => CoreVersion::new(0, 21, 0),
+            Feature::MaxBurnAmount => CoreVersion::new(25, 0, 0),
+            Feature::SubmitPackage => CoreVersion::new(26, 0, 0),
+            Feature::Truc => CoreVersion::new(28, 0, 0),
+            Feature::TxSpendingPrevout => CoreVersion::new(24, 0, 0),
+        }
+    }
+}
+
+/// What the connected node supports.
+#[derive(Debug, Clone, Copy)]
+pub struct Capabilities {
+    pub version: CoreVersion,
+}
+
+impl Capabilities {
+    /// Asks the node behind `rpc` which version it runs.
+    pub fn query(rpc: &impl RpcApi) -> Result<Self> {
+        let info = rpc.get_network_info()?;
+        Ok(Capabilities {
+            version: CoreVersion::from_network_info(info.version),
+        })
+    }
+
+    pub fn has(&self, feature: Feature) -> bool {
+        self.version >= feature.min_version()
+    }
+
+    /// Fails with a "requires Bitcoin Core ≥ X" error instead of letting the
+    /// node answer with a bare "Method not found".
+    pub fn require(&self, feature: Feature) -> Result<()> {
+        if !self.has(feature) {
+            bail!(
+                "{} requires Bitcoin Core ≥ {} (connected node is {})",
+                feature.name(),
+                feature.min_version(),
+                self.version
+            );
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Capabilities {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Bitcoin Core {}:", self.version)?;
+        for feature in Feature::ALL {
+            write!(f, " {} {}", feature.name(), if self.has(feature) { "✅" } else { "❌" })?;
+        }
+        Ok(())
+    }
+}
+
+impl Node {
+    /// What the connected node supports, as fetched at connect.
+    pub fn node_capabilities(&self) -> Capabilities {
+        self.context.capabilities
+    }
+}
@@ -0,0 +1,105 @@
+//! `--emit-script`: a run replayed as `bitcoin-cli` commands, for workshops.
+//! [`MeteredClient`](crate::bench::MeteredClient) records every call that
+//! changes something or builds, signs or tests a transaction, with the exact
+//! parameters (raw transaction hex included) the demo used.
+
+use serde_json::Value;
+
+/// RPCs worth replaying by hand; reads like `getmempoolentry` are left out.
+const REPLAYED: [&str; 17] = [
+    "createwallet",
+    "loadwallet",
+    "generatetoaddress",
+    "generateblock",
+    "sendtoaddress",
+    "createrawtransaction",
+    "fundrawtransaction",
+    "converttopsbt",
+    "walletprocesspsbt",
+    "finalizepsbt",
+    "signrawtransactionwithwallet",
+    "signrawtransactionwithkey",
+    "testmempoolaccept",
+    "sendrawtransaction",
+    "submitpackage",
+    "bumpfee",
+    "importdescriptors",
+];
+
+/// One `bitcoin-cli` invocation.
+#[derive(Debug, Clone)]
+pub struct CliCommand {
+    /// `-rpcwallet` to run it against, `None` for node-level RPCs.
+    pub wallet: Option<String>,
+    pub method: String,
+    pub args: Vec<Value>,
+}
+
+impl CliCommand {
+    /// Whether a call to `method` belongs in the emitted script.
+    pub fn is_replayed(method: &str) -> bool {
+        REPLAYED.contains(&method)
+    }
+}
+
+/// Quotes `arg` for a POSIX shell: strings as-is, everything else as JSON.
+fn shell_arg(arg: &Value) -> String {
+    let raw = match arg {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    format!("'{}'", raw.replace('\'', r"'\''"))
+}
+
+/// Renders `steps` as a POSIX shell script, one `bitcoin-cli` call per line.
+pub fn emit_cli_script(steps: &[CliCommand]) -> String {
+    let mut script = String::from(
+        "#!/bin/sh\n\
+         # bitcoin-cli replay of an accelerate-txs-demo run, in the order the demo\n\
+         # made the calls. Set BITCOIN_CLI to reach your node (e.g. add -rpcuser/-rpcpassword).\n\
+         # Signatures and txids only match on a fresh regtest chain with --deterministic.\n\
+         BITCOIN_CLI=\"${BITCOIN_CLI:-bitcoin-cli -regtest}\"\n\n",
+    );
+    for step in steps {
+        script.push_str("$BITCOIN_CLI");
+        if let Some(wallet) = &step.wallet {
+            script.push_str(&format!(" -rpcwallet={}", shell_arg(&Value::String(wallet.clone()))));
+        }
+        script.push(' ');
+        script.push_str(&step.method);
+        for arg in &step.args {
+            script.push(' ');
+            script.push_str(&shell_arg(arg));
+        }
+        script.push('\n');
+    }
+    script
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// A single quote inside an argument closes the quoting, escapes itself
+    /// and reopens it, so the shell sees the original string.
+    #[test]
+    fn single_quotes_escaped() {
+        assert_eq!(shell_arg(&json!("it's")), r"'it'\''s'");
+        assert_eq!(shell_arg(&json!("plain")), "'plain'");
+        assert_eq!(shell_arg(&json!({ "label": "o'brien" })), r#"'{"label":"o'\''brien"}'"#);
+        assert_eq!(shell_arg(&json!(1.5)), "'1.5'");
+    }
+
+    #[test]
+    fn one_cli_call_per_step() {
+        let steps = [
+            CliCommand { wallet: None, method: "generatetoaddress".into(), args: vec![json!(1), json!("bcrt1q")] },
+            CliCommand { wallet: Some("w'1".into()), method: "sendrawtransaction".into(), args: vec![json!("00")] },
+        ];
+        let script = emit_cli_script(&steps);
+        assert!(script.starts_with("#!/bin/sh\n"));
+        assert!(script.contains("\n$BITCOIN_CLI generatetoaddress '1' 'bcrt1q'\n"));
+        assert!(script.ends_with("\n$BITCOIN_CLI -rpcwallet='w'\\''1' sendrawtransaction '00'\n"));
+    }
+}
@@ -0,0 +1,79 @@
+//! Protocol parameters shared by the RBF, CPFP and P2A demos.
+
+use bitcoin::transaction::Version;
+use bitcoin::{Amount, Sequence};
+
+/// Witness program pushed by a Pay-to-Anchor output (`OP_1 <0x4e73>`).
+pub const P2A_WITNESS_PROGRAM: [u8; 2] = [0x4e, 0x73];
+
+/// The full standard P2A scriptPubKey: `OP_1 OP_PUSHBYTES_2 4e73`.
+pub const P2A_SCRIPT_BYTES: [u8; 4] = [0x51, 0x02, 0x4e, 0x73];
+
+/// Dust threshold for a P2A output that is *not* ephemeral.
+pub const P2A_DUST_SAT: u64 = 240;
+
+/// Dust threshold for a P2WPKH output at the default 3 sat/vB dust relay fee.
+pub const P2WPKH_DUST_SAT: u64 = 294;
+
+/// Largest script the interpreter will run (`MAX_SCRIPT_SIZE`).
+pub const MAX_SCRIPT_SIZE: usize = 10_000;
+
+/// Confirmations a coinbase output needs before it can be spent.
+pub const COINBASE_MATURITY: u32 = 100;
+
+/// TRUC (Topologically Restricted Until Confirmation) transaction version.
+pub const TRUC_VERSION: Version = Version(3);
+
+/// Largest vsize a TRUC child (a v3 transaction with an unconfirmed parent) may have.
+pub const TRUC_CHILD_MAX_VSIZE: usize = 1_000;
+
+/// Default `-limitdescendantcount`: most in-mempool descendants a transaction
+/// may have, itself included.
+pub const DEFAULT_DESCENDANT_LIMIT: u64 = 25;
+
+/// Largest child the CPFP carve-out lets past the descendant limit.
+pub const CARVE_OUT_MAX_VSIZE: usize = 10_000;
+
+/// vsize an anchor input adds to a child: outpoint, empty scriptSig, sequence.
+pub const ANCHOR_INPUT_VSIZE: usize = 41;
+
+/// vsize of a P2A output: value, script length, `OP_1 <4e73>`.
+pub const P2A_OUTPUT_VSIZE: usize = 13;
+
+/// vsize of a P2WPKH output: value, script length, `OP_0 <20 bytes>`.
+pub const P2WPKH_OUTPUT_VSIZE: usize = 31;
+
+/// vsize of a P2WPKH input: outpoint, empty scriptSig, sequence and a
+/// signature plus pubkey witness at a quarter each.
+pub const P2WPKH_INPUT_VSIZE: usize = 68;
+
+/// Feerate (sat/vB) the demos aim for when the node has no fee estimate yet,
+/// as on a fresh regtest chain.
+pub const FALLBACK_TARGET_FEERATE: f64 = 10.0;
+
+/// Most fee UTXO candidates offered to the user at once.
+pub const MAX_FEE_CANDIDATES: usize = 10;
+
+/// Default `incrementalrelayfee` (per kvB): the minimum feerate bump a BIP125
+/// replacement must pay on top of the fees it evicts. Nodes can set their own;
+/// [`crate::context::NodeContext::incremental_fee`] has the one in force.
+pub const DEFAULT_INCREMENTAL_RELAY_FEE: Amount = Amount::from_sat(1_000);
+
+/// `nSequence` that signals BIP125 replaceability.
+pub const SEQUENCE_RBF: Sequence = Sequence::ENABLE_RBF_NO_LOCKTIME;
+
+/// `nSequence` that enables locktime but does not signal RBF.
+pub const SEQUENCE_NO_RBF: Sequence = Sequence::ENABLE_LOCKTIME_NO_RBF;
+
+/// Final `nSequence`: no RBF, no locktime.
+pub const SEQUENCE_FINAL: Sequence = Sequence::MAX;
+
+/// Wallet label on the per-run change address.
+pub const CHANGE_LABEL: &str = "change";
+
+/// Receive descriptor of the `--deterministic` wallet: BIP84 on the master key
+/// of the BIP32 test vector 1 seed (`000102...0f`). Publicly known, regtest only.
+pub const DETERMINISTIC_DESCRIPTOR: &str = "wpkh(tprv8ZgxMBicQKsPeDgjzdC36fs6bMjGApWDNLR9erAXMs5skhMv36j9MV5ecvfavji5khqjWaWSFhN3YcCUUdiKH6isR4Pwy3U5y5egddBr16m/84h/1h/0h/0/*)";
+
+/// Wallet `--deterministic` runs use, so they never mix with the random one.
+pub const DETERMINISTIC_WALLET: &str = "deterministic_demo_wallet";
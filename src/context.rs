@@ -0,0 +1,41 @@
+//! What stays fixed about the node for a whole run: which chain it follows,
+//! its version and relay policy. Fetched once at connect; volatile state
+//! (mempool, balance, tip) is still queried fresh every time.
+
+use anyhow::Result;
+use bitcoin::{Amount, Network};
+use bitcoincore_rpc::RpcApi;
+
+use crate::capabilities::{Capabilities, CoreVersion};
+
+/// The node's static facts, shared by every flow of a run.
+pub struct NodeContext {
+    pub chain: Network,
+    pub capabilities: Capabilities,
+    /// `minrelaytxfee`, per kvB.
+    pub relay_fee: Amount,
+    /// `incrementalrelayfee`, per kvB: what BIP125 charges per replacement vbyte.
+    pub incremental_fee: Amount,
+    /// Chain height when the context was fetched; stale afterwards.
+    pub start_height: u64,
+}
+
+impl NodeContext {
+    /// `minrelaytxfee` in sat/vB.
+    pub fn min_relay_feerate(&self) -> f64 {
+        self.relay_fee.to_sat() as f64 / 1000.0 // per kvB → sat/vB
+    }
+}
+
+/// Fetches the [`NodeContext`] in two calls (`getblockchaininfo`, `getnetworkinfo`).
+pub fn context(rpc: &impl RpcApi) -> Result<NodeContext> {
+    let blockchain_info = rpc.get_blockchain_info()?;
+    let network_info = rpc.get_network_info()?;
+    Ok(NodeContext {
+        chain: blockchain_info.chain,
+        capabilities: Capabilities { version: CoreVersion::from_network_info(network_info.version) },
+        relay_fee: network_info.relay_fee,
+        incremental_fee: network_info.incremental_fee,
+        start_height: blockchain_info.blocks,
+    })
+}
@@ -1,33 +1,335 @@
-use anyhow::Result;
-use bitcoin::Amount;
-use bitcoincore_rpc::{Auth, Client, RpcApi};
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use bitcoin::constants::WITNESS_SCALE_FACTOR;
+use bitcoin::opcodes::all::{OP_CHECKSIG, OP_CSV, OP_DROP};
+use bitcoin::script::Builder;
+use bitcoin::secp256k1::Secp256k1;
+use bitcoin::{Address, Amount, BlockHash, Network, OutPoint, PrivateKey, ScriptBuf, Sequence, Transaction, Txid};
+use bitcoincore_rpc::json::{GetMempoolEntryResult, SignRawTransactionResult};
+use bitcoincore_rpc::RpcApi;
+use serde::Deserialize;
+use serde_json::json;
 use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
 
-pub async fn run_demo() -> Result<()> {
-    println!("🚀 CPFP Demo - Child-Pays-for-Parent\n");
+use crate::capabilities::Feature;
+use crate::consts::{CARVE_OUT_MAX_VSIZE, DEFAULT_DESCENDANT_LIMIT, FALLBACK_TARGET_FEERATE, SEQUENCE_FINAL, SEQUENCE_NO_RBF, SEQUENCE_RBF, TRUC_VERSION};
+use crate::deadline::Method;
+use crate::dot;
+use crate::dump;
+use crate::errors::FriendlyError;
+use crate::fees;
+use crate::fmt::{btc, feerate, sats};
+use crate::mempool::mempool_delta;
+use crate::narrative::{DemoStep, Story};
+use crate::node::Node;
+use crate::options::Options;
+use crate::{rbf, sim};
 
-    // Connect to regtest bitcoind
-    let rpc_base = Client::new("http://127.0.0.1:18443", Auth::UserPass("user".to_string(), "pass".to_string()))?;
-    
-    // Check regtest is running
-    let blockchain_info = rpc_base.get_blockchain_info()?;
-    println!("✅ Connected to Bitcoin Core (regtest)");
-    println!("   └─ Chain: {}, Blocks: {}\n", blockchain_info.chain, blockchain_info.blocks);
-
-    // Try to load existing wallet or create new one
-    let wallet_name = "rbf_demo_wallet";
-    match rpc_base.load_wallet(wallet_name) {
-        Ok(_) => println!("💼 Loaded existing wallet"),
-        Err(_) => {
-            match rpc_base.create_wallet(wallet_name, None, None, None, None) {
-                Ok(_) => println!("💼 Created new wallet"),
-                Err(_) => println!("💼 Using existing wallet"),
+/// Whether the parent and the child spending it made it into the mined block.
+pub struct CpfpOutcome {
+    pub parent_txid: Txid,
+    pub child_txid: Txid,
+    pub parent_fee: Amount,
+    pub child_fee: Amount,
+    /// sat/vB of the parent alone and of parent and child together.
+    pub parent_feerate: f64,
+    pub package_feerate: f64,
+    /// Hash and height of the block mined (or waited for) at the end.
+    pub block: (BlockHash, u64),
+    pub parent_confirmed: bool,
+    pub child_confirmed: bool,
+    /// The package made it into a size-limited block that left competing,
+    /// higher-feerate-than-the-parent transactions behind.
+    pub beat_competition: bool,
+}
+
+impl fmt::Display for CpfpOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "CPFP: parent {} ({}) ← child {} ({}), {}",
+            self.parent_txid,
+            if self.parent_confirmed { "confirmed" } else { "unconfirmed" },
+            self.child_txid,
+            if self.child_confirmed { "confirmed" } else { "unconfirmed" },
+            if self.beat_competition { "beat the competition" } else { "did NOT beat the competition" },
+        )
+    }
+}
+
+/// Transactions broadcast to compete with the package for a limited block.
+const COMPETITOR_COUNT: usize = 3;
+
+/// Fee each extra generation pays with `--cpfp-depth`.
+const CHAIN_FEE_EACH: Amount = Amount::from_sat(10_000);
+
+/// The stuck parent's feerate (sat/vB).
+const PARENT_FEERATE: f64 = 10.0;
+
+/// Stuck parents the batch demo accelerates concurrently.
+const BATCH_PARENTS: usize = 3;
+
+#[derive(Deserialize)]
+struct GenerateBlockResult {
+    hash: BlockHash,
+}
+
+/// The mempool rule that let a CPFP child in next to its parent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CpfpMechanism {
+    /// Both v3: TRUC allows exactly one child, and a better one evicts it.
+    Truc,
+    /// The parent was already at the descendant limit; the carve-out admitted
+    /// one more small child with no other unconfirmed ancestor.
+    CarveOut,
+    /// Within the ordinary ancestor/descendant limits.
+    DescendantLimits,
+}
+
+impl fmt::Display for CpfpMechanism {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CpfpMechanism::Truc => write!(f, "TRUC (v3): one parent, one child, sibling eviction"),
+            CpfpMechanism::CarveOut => write!(
+                f,
+                "CPFP carve-out: one extra child past the descendant limit (assuming the default -limitdescendantcount={})",
+                DEFAULT_DESCENDANT_LIMIT
+            ),
+            CpfpMechanism::DescendantLimits => write!(
+                f,
+                "ordinary ancestor/descendant limits, no carve-out needed (assuming the default -limitdescendantcount={})",
+                DEFAULT_DESCENDANT_LIMIT
+            ),
+        }
+    }
+}
+
+/// The transaction-level half of the pre-TRUC CPFP carve-out: `child` spends
+/// `parent`, is at most [`CARVE_OUT_MAX_VSIZE`] vB, and neither is v3 (TRUC
+/// has its own topology rules). The mempool half, that `parent` is the child's
+/// only unconfirmed ancestor, is [`Node::cpfp_mechanism`]'s to check.
+pub fn carve_out_eligible(parent: &Transaction, child: &Transaction) -> bool {
+    let parent_txid = parent.compute_txid();
+    child.input.iter().any(|input| input.previous_output.txid == parent_txid)
+        && child.vsize() <= CARVE_OUT_MAX_VSIZE
+        && parent.version != TRUC_VERSION
+        && child.version != TRUC_VERSION
+}
+
+fn ancestor_feerate(entry: &GetMempoolEntryResult) -> f64 {
+    sim::feerate(entry.fees.ancestor, entry.ancestor_size as usize)
+}
+
+/// Appends `txid` to `out` after any of its in-mempool ancestors that aren't
+/// already in `selected` or `out`, so the result is in valid block order.
+fn push_with_ancestors(txid: Txid, mempool: &HashMap<Txid, GetMempoolEntryResult>, selected: &[Txid], out: &mut Vec<Txid>) {
+    if selected.contains(&txid) || out.contains(&txid) {
+        return;
+    }
+    for parent in &mempool[&txid].depends {
+        push_with_ancestors(*parent, mempool, selected, out);
+    }
+    out.push(txid);
+}
+
+/// A P2WSH output that only becomes spendable `blocks` after it confirms:
+/// `<blocks> OP_CSV OP_DROP <pubkey> OP_CHECKSIG`, keyed by a throwaway key.
+struct CsvLock {
+    blocks: u16,
+    key: PrivateKey,
+    witness_script: ScriptBuf,
+}
+
+impl CsvLock {
+    fn new(blocks: u16, network: Network) -> Self {
+        let key = PrivateKey::generate(network);
+        let witness_script = Builder::new()
+            .push_int(blocks.into())
+            .push_opcode(OP_CSV)
+            .push_opcode(OP_DROP)
+            .push_key(&key.public_key(&Secp256k1::new()))
+            .push_opcode(OP_CHECKSIG)
+            .into_script();
+        CsvLock { blocks, key, witness_script }
+    }
+
+    fn address(&self, network: Network) -> Address {
+        Address::p2wsh(&self.witness_script, network)
+    }
+
+    /// The `nSequence` a spending input needs to satisfy the lock.
+    fn sequence(&self) -> Sequence {
+        Sequence::from_height(self.blocks)
+    }
+}
+
+impl Node {
+    /// Signs a spend of a [`CsvLock`] output with the lock's own key.
+    fn sign_csv_spend(&self, tx: &bitcoin::Transaction, lock: &CsvLock, prevout: OutPoint, value: Amount, network: Network) -> Result<SignRawTransactionResult> {
+        let prevtxs = json!([{
+            "txid": prevout.txid,
+            "vout": prevout.vout,
+            "scriptPubKey": lock.address(network).script_pubkey().to_hex_string(),
+            "witnessScript": lock.witness_script.to_hex_string(),
+            "amount": value.to_btc(),
+        }]);
+        let raw = bitcoin::consensus::encode::serialize_hex(tx);
+        Ok(self.rpc.call("signrawtransactionwithkey", &[json!(raw), json!([lock.key.to_wif()]), prevtxs])?)
+    }
+
+    /// Uses `testmempoolaccept` to show `tx` is non-final, mining one block at a
+    /// time until its relative timelock is satisfied.
+    fn mine_until_final(&self, tx: &Transaction, blocks: u16, miner_addr: &Address) -> Result<()> {
+        for mined in 0..=u32::from(blocks) {
+            let result = &self.rpc.test_mempool_accept(&[tx])?[0];
+            if result.allowed {
+                say!("   └─ ✅ Final after {} block(s): child now accepted", mined);
+                return Ok(());
+            }
+            say!("   ├─ ⏳ Rejected after {} block(s): {} ({} more to go)",
+                     mined, result.reject_reason.as_deref().unwrap_or("unknown"), self.blocks_until_final(tx)?);
+            self.rpc.generate_to_address(1, miner_addr)?;
+        }
+        bail!("child still non-final after {} blocks", blocks)
+    }
+
+    /// Broadcasts `count` self-payments from confirmed wallet UTXOs at roughly
+    /// `rate` sat/vB, each independent of every other mempool transaction.
+    fn broadcast_competitors(&self, count: usize, rate: f64, dest: &Address) -> Result<Vec<Txid>> {
+        let fee = sim::fee_from_feerate(sim::TYPICAL_CHILD_VSIZE * WITNESS_SCALE_FACTOR, rate);
+        let mut txids = Vec::with_capacity(count);
+        for utxo in self.rpc.list_unspent(Some(1), None, None, None, None)? {
+            if txids.len() == count {
+                break;
+            }
+            if utxo.amount <= fee * 2 || !self.is_mature(&utxo)? {
+                continue;
+            }
+            let tx = self.tx_builder()?
+                .input(OutPoint::new(utxo.txid, utxo.vout), SEQUENCE_NO_RBF)
+                .pay(utxo.amount - fee, dest)
+                .build();
+            let signed = self.sign_with_wallet(&tx)?;
+            txids.push(self.broadcast(&signed.hex, None)?);
+        }
+        ensure!(txids.len() == count, "only found {} confirmed UTXOs for {} competitors", txids.len(), count);
+        Ok(txids)
+    }
+
+    /// Extends a CPFP package with up to `depth` more generations, each spending
+    /// output 0 of the previous one and paying `fee_each`. Stops at the first
+    /// child the node rejects for exceeding its chain limits
+    /// (`-limitdescendantcount`, `-limitancestorcount`) and reports why.
+    pub fn build_cpfp_chain(&self, root: OutPoint, depth: usize, fee_each: Amount, dest: &Address) -> Result<Vec<Txid>> {
+        let mut txids = Vec::with_capacity(depth);
+        let mut prevout = root;
+        for generation in 1..=depth {
+            let value = self.rpc.get_tx_out(&prevout.txid, prevout.vout, Some(true))?
+                .ok_or_else(|| anyhow!("{} is not an unspent output", prevout))?
+                .value;
+            let send_amount = value.checked_sub(fee_each)
+                .ok_or_else(|| anyhow!("{} ({}) cannot cover another {} fee", prevout, btc(value), btc(fee_each)))?;
+            let tx = self.tx_builder()?
+                .input(prevout, SEQUENCE_NO_RBF)
+                .pay(send_amount, dest)
+                .build();
+            let signed = self.sign_with_wallet(&tx)?;
+            match self.broadcast(&signed.hex, None) {
+                Ok(txid) => {
+                    say!("   ├─ Generation {}: {}", generation, txid);
+                    txids.push(txid);
+                    prevout = OutPoint::new(txid, 0);
+                }
+                Err(e) if matches!(e.downcast_ref(), Some(FriendlyError::TooLongMempoolChain(_))) => {
+                    say!("   ├─ ⛔ Generation {} rejected: {}", generation, e);
+                    break;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(txids)
+    }
+
+    /// Which rule admitted `child`, already in the mempool with `parent`:
+    /// TRUC when both are v3, otherwise the carve-out if `parent` has more
+    /// descendants than the default limit allows and `child` qualifies. No RPC
+    /// reports the node's own `-limitdescendantcount`, so the default is assumed.
+    pub fn cpfp_mechanism(&self, parent: &Transaction, child: &Transaction) -> Result<CpfpMechanism> {
+        if parent.version == TRUC_VERSION && child.version == TRUC_VERSION {
+            return Ok(CpfpMechanism::Truc);
+        }
+        let parent_entry = self.rpc.get_mempool_entry(&parent.compute_txid())?;
+        let child_entry = self.rpc.get_mempool_entry(&child.compute_txid())?;
+        let only_ancestor = child_entry.ancestor_count == 2;
+        if carve_out_eligible(parent, child) && only_ancestor && parent_entry.descendant_count > DEFAULT_DESCENDANT_LIMIT {
+            Ok(CpfpMechanism::CarveOut)
+        } else {
+            Ok(CpfpMechanism::DescendantLimits)
+        }
+    }
+
+    /// Mines a block (`generateblock`) of only the mempool transactions whose
+    /// ancestor feerate reaches `cutoff` sat/vB, with their ancestors: a miner
+    /// with plenty of better-paying transactions to fill the rest.
+    pub fn mine_above_feerate(&self, cutoff: f64, miner_addr: &Address) -> Result<BlockHash> {
+        self.node_capabilities().require(Feature::GenerateBlock)?;
+        let mempool = self.rpc.get_raw_mempool_verbose()?;
+        let mut selected = Vec::new();
+        for (txid, entry) in &mempool {
+            if ancestor_feerate(entry) >= cutoff {
+                let mut package = Vec::new();
+                push_with_ancestors(*txid, &mempool, &selected, &mut package);
+                selected.extend(package);
             }
         }
+        let result: GenerateBlockResult = self.rpc.call("generateblock", &[json!(miner_addr.to_string()), json!(selected)])?;
+        Ok(result.hash)
     }
 
-    // Connect to the specific wallet
-    let rpc = Client::new(&format!("http://127.0.0.1:18443/wallet/{}", wallet_name), Auth::UserPass("user".to_string(), "pass".to_string()))?;
+    /// Mines a block holding at most `max_vsize` vbytes of mempool transactions,
+    /// chosen greedily by ancestor feerate as a miner would, via `generateblock`.
+    fn mine_feerate_limited_block(&self, max_vsize: u64, miner_addr: &Address) -> Result<BlockHash> {
+        self.node_capabilities().require(Feature::GenerateBlock)?;
+        let mempool = self.rpc.get_raw_mempool_verbose()?;
+        let mut candidates: Vec<_> = mempool.iter().collect();
+        candidates.sort_by(|(_, a), (_, b)| ancestor_feerate(b).total_cmp(&ancestor_feerate(a)));
+
+        let mut selected = Vec::new();
+        let mut used = 0;
+        for (txid, _) in candidates {
+            let mut package = Vec::new();
+            push_with_ancestors(*txid, &mempool, &selected, &mut package);
+            let size: u64 = package.iter().map(|t| mempool[t].vsize).sum();
+            if used + size <= max_vsize {
+                used += size;
+                selected.extend(package);
+            }
+        }
+
+        let result: GenerateBlockResult = self.rpc.call("generateblock", &[json!(miner_addr.to_string()), json!(selected)])?;
+        Ok(result.hash)
+    }
+}
+
+pub async fn run_demo(opts: &Options) -> Result<Option<CpfpOutcome>> {
+    say!("🚀 CPFP Demo - Child-Pays-for-Parent\n");
+    let mut story = Story::new(vec![
+        DemoStep::step(1, "Creating PARENT transaction with LOW fee"),
+        DemoStep::pause(
+            &["Parent transaction is stuck with very low fee!", "It cannot use RBF (sequence = 0xffffffff)", "But we can use CPFP to accelerate it!"],
+            &["Press Enter to create CHILD transaction..."],
+        ),
+        DemoStep::step(2, "Creating CHILD transaction with HIGH fee"),
+        DemoStep::final_demo(
+            "Regtest mines everything, so let's make block space SCARCE:",
+            &["competitors will outbid the parent alone, but not the package...", "Press Enter to mine block..."],
+        ),
+    ], opts.interactive);
+
+    let node = Node::connect(opts)?;
+    let rpc = &node.rpc;
+    node.print_fee_histogram()?;
 
     // Get addresses
     let funding_addr = rpc.get_new_address(None, None)?.assume_checked();
@@ -35,176 +337,485 @@ pub async fn run_demo() -> Result<()> {
     let final_addr = rpc.get_new_address(None, None)?.assume_checked();
     
     // Fund wallet if needed
-    let balance = rpc.get_balance(None, None)?;
-    if balance.to_btc() < 10.0 {
-        println!("⛏️  Mining blocks for funding...");
-        rpc.generate_to_address(101, &funding_addr)?;
-        let new_balance = rpc.get_balance(None, None)?;
-        println!("   └─ Balance: {} BTC\n", new_balance);
-    } else {
-        println!("💰 Wallet balance: {} BTC\n", balance);
-    }
+    node.fund_if_needed(&funding_addr)?;
+    node.flood_if_requested()?;
 
     // Get a UTXO to create our parent transaction
-    let unspent = rpc.list_unspent(None, None, None, None, None)?;
-    if unspent.is_empty() || unspent[0].amount.to_btc() < 1.0 {
-        println!("❌ Need larger UTXOs, mining more blocks...");
-        rpc.generate_to_address(100, &funding_addr)?;
-        return Ok(());
-    }
-
-    let utxo = &unspent[0];
-    println!("🎯 Using UTXO: {}:{} ({} BTC)", utxo.txid, utxo.vout, utxo.amount);
+    let utxo = node.spendable_utxo(Amount::ONE_BTC)?;
+    say!("🎯 Using UTXO: {}:{} ({})", utxo.txid, utxo.vout, btc(utxo.amount));
+    node.lock_utxo(OutPoint::new(utxo.txid, utxo.vout))?;
 
     // === STEP 1: Create Parent Transaction (Low Fee) ===
-    println!("\n📝 STEP 1: Creating PARENT transaction with LOW fee");
+    story.advance()?;
     
-    // Calculate amounts based on actual UTXO
-    let utxo_amount = utxo.amount.to_btc();
-    let parent_fee = 0.0001; // Very small fee
-    let parent_send_amount = utxo_amount - parent_fee;
-
-    println!("   ├─ Input: {}:{} ({} BTC)", utxo.txid, utxo.vout, utxo_amount);
-    println!("   ├─ Output: {} BTC to intermediate address", parent_send_amount);
-    println!("   ├─ Fee: {} BTC (VERY LOW)", parent_fee);
-    println!("   └─ RBF: DISABLED (can't be replaced)\n");
-
-    // Create parent transaction
-    let parent_inputs = vec![bitcoincore_rpc::json::CreateRawTransactionInput {
-        txid: utxo.txid,
-        vout: utxo.vout,
-        sequence: Some(0xffffffff), // NO RBF - final sequence
-    }];
-
-    let mut parent_outputs = HashMap::new();
-    parent_outputs.insert(intermediate_addr.to_string(), Amount::from_btc(parent_send_amount)?);
-
-    // Create and sign parent transaction
-    let parent_raw = rpc.create_raw_transaction(&parent_inputs, &parent_outputs, None, Some(false))?;
-    let parent_signed = rpc.sign_raw_transaction_with_wallet(&parent_raw, None, None)?;
+    // Size the output the child spends for every fee it may pay, not the whole UTXO
+    let child_fee = Amount::from_sat(1_000_000); // High fee for acceleration
+    let chain_fees = CHAIN_FEE_EACH * (opts.cpfp_depth as u64 - 1);
+    // Twice the child fee leaves room for --replaceable-child's bump
+    let child_fee_budget = child_fee * 2 + chain_fees;
+    let (parent_send_amount, parent_fee) = sim::plan_parent_for_cpfp(utxo.amount, PARENT_FEERATE, child_fee_budget)
+        .with_context(|| format!("UTXO {} is too small for the CPFP parent", utxo.txid))?;
+    let parent_change = utxo.amount - parent_send_amount - parent_fee;
+    node.confirm_mainnet_fees(&[
+        ("Parent fee", parent_fee),
+        ("Child fee", child_fee),
+        ("Chain fees", chain_fees),
+    ])?;
+
+    say!("   ├─ Input: {}:{} ({})", utxo.txid, utxo.vout, btc(utxo.amount));
+    say!("   ├─ Output: {} to intermediate address (child fee budget {} + dust)", btc(parent_send_amount), btc(child_fee_budget));
+    say!("   ├─ Change: {}", btc(parent_change));
+    say!("   ├─ Fee: {} (VERY LOW, {})", btc(parent_fee), feerate(PARENT_FEERATE));
+    say!("   └─ RBF: DISABLED (can't be replaced)\n");
+
+    // With --csv the parent pays into a relative-timelocked script instead
+    let network = node.context.chain;
+    let csv_lock = opts.csv.map(|blocks| CsvLock::new(blocks, network));
+    let parent_dest = match &csv_lock {
+        Some(lock) => {
+            say!("🔒 Parent output locked with {} OP_CSV (P2WSH)\n", lock.blocks);
+            lock.address(network)
+        }
+        None => intermediate_addr.clone(),
+    };
+
+    // Create and sign parent transaction; the child spends output 0
+    let mut parent = node.tx_builder()?
+        .input(OutPoint::new(utxo.txid, utxo.vout), SEQUENCE_FINAL) // NO RBF - final sequence
+        .pay(parent_send_amount, &parent_dest);
+    if parent_change > Amount::ZERO {
+        parent = parent.pay(parent_change, node.change_address()?);
+    }
+    let parent = parent.build();
+    let parent_signed = node.sign_with_wallet(&parent)?;
 
     // Broadcast parent transaction
-    let parent_txid = rpc.send_raw_transaction(&parent_signed.hex)?;
-    println!("✅ Parent TX broadcasted: {}", parent_txid);
-    println!("   ├─ Creates: {} BTC output for child to spend", parent_send_amount);
-    println!("   ├─ Fee: {} BTC (very low)", parent_fee);
-    println!("   └─ RBF: DISABLED");
+    let before_parent = rpc.get_raw_mempool()?;
+    let parent_txid = node.broadcast(&parent_signed.hex, None)?;
+    say!("✅ Parent TX broadcasted: {}", parent_txid);
+    say!("   ├─ Creates: {} output for child to spend", btc(parent_send_amount));
+    say!("   ├─ Fee: {} (very low)", btc(parent_fee));
+    say!("   ├─ Size: {}", sim::size_breakdown(&parent_signed.transaction()?));
+    say!("   └─ RBF: DISABLED");
+    node.verify_fee(parent_txid, parent_fee)?;
 
     // Check mempool
     let mempool = rpc.get_raw_mempool()?;
-    println!("\n🔍 Mempool: {} transactions", mempool.len());
-    println!("   └─ Contains parent: {}", mempool.contains(&parent_txid));
+    say!("\n🔍 Mempool: {} transactions", mempool.len());
+    say!("{}", mempool_delta(&before_parent, &mempool));
 
     // Pause for presentation
-    println!("\n⏸️  [PRESENTATION MOMENT]");
-    println!("💡 Parent transaction is stuck with very low fee!");
-    println!("💡 It cannot use RBF (sequence = 0xffffffff)");
-    println!("💡 But we can use CPFP to accelerate it!");
-    println!("   Press Enter to create CHILD transaction...");
-    let mut input = String::new();
-    std::io::stdin().read_line(&mut input)?;
+    node.print_cost_preview(OutPoint::new(parent_txid, 0), Method::Cpfp)?;
+    story.advance()?;
+
+    // A long pause can let the parent confirm, and then there's nothing to accelerate
+    let parent_already_confirmed = rpc.get_mempool_entry(&parent_txid).is_err();
+    if parent_already_confirmed {
+        ensure!(
+            rpc.get_transaction(&parent_txid, None)?.info.confirmations > 0,
+            "parent {} left the mempool without confirming", parent_txid
+        );
+        say!("⚠️  Parent {} already CONFIRMED: CPFP isn't needed any more", parent_txid);
+        say!("   The child would just be a normal spend of a confirmed output.");
+        say!("   Type 'spend' + Enter to continue anyway, anything else aborts...");
+        if opts.prompt()?.trim() != "spend" {
+            say!("🛑 CPFP demo aborted: nothing left to accelerate");
+            return Ok(None);
+        }
+    }
 
     // === STEP 2: Create Child Transaction (High Fee) ===
-    println!("📝 STEP 2: Creating CHILD transaction with HIGH fee");
+    story.advance()?;
 
     // Child spends ALL of the parent output minus a high fee
-    let child_fee = 0.01; // High fee for acceleration
-    let child_send_amount = ((parent_send_amount - child_fee) * 100_000_000.0).round() / 100_000_000.0; // Round to 8 decimals
+    let child_send_amount = parent_send_amount - child_fee;
 
-    println!("   ├─ Input: Parent's {} BTC output ({}:0)", parent_send_amount, parent_txid);
-    println!("   ├─ Output: {} BTC to final address", child_send_amount);
-    println!("   ├─ Fee: {} BTC (100x HIGHER than parent!)", child_fee);
-    println!("   └─ Effect: Accelerates BOTH parent and child\n");
+    say!("   ├─ Input: Parent's {} output ({}:0)", btc(parent_send_amount), parent_txid);
+    say!("   ├─ Output: {} to final address", btc(child_send_amount));
+    say!("   ├─ Fee: {} (100x HIGHER than parent!)", btc(child_fee));
+    say!("   ├─ RBF: {}", if opts.replaceable_child { "ENABLED (--replaceable-child)" } else { "DISABLED" });
+    say!("   └─ Effect: Accelerates BOTH parent and child\n");
 
-    // Create child transaction
-    let child_inputs = vec![bitcoincore_rpc::json::CreateRawTransactionInput {
-        txid: parent_txid,
-        vout: 0, // Spend the parent's output
-        sequence: Some(0xfffffffe),
-    }];
+    // Create and sign a child paying `fee` (also used to replace it below)
+    let child_sequence = match &csv_lock {
+        // A CSV-locked output needs the matching relative-lock sequence
+        Some(lock) => lock.sequence(),
+        None if opts.replaceable_child => SEQUENCE_RBF,
+        None => SEQUENCE_NO_RBF,
+    };
+    let build_child = |fee: Amount| -> Result<SignRawTransactionResult> {
+        let child = node.tx_builder()?
+            .input(OutPoint::new(parent_txid, 0), child_sequence) // Spend the parent's output
+            .pay(parent_send_amount - fee, &final_addr)
+            .build();
+        match &csv_lock {
+            Some(lock) => node.sign_csv_spend(&child, lock, OutPoint::new(parent_txid, 0), parent_send_amount, network),
+            None => node.sign_with_wallet(&child),
+        }
+    };
+    let child_signed = build_child(child_fee)?;
 
-    let mut child_outputs = HashMap::new();
-    child_outputs.insert(final_addr.to_string(), Amount::from_btc(child_send_amount)?);
+    // Preview the package with the offline simulator before broadcasting
+    let parent_vsize = parent_signed.transaction()?.vsize();
+    let child_vsize = child_signed.transaction()?.vsize();
+    let package_rate = sim::package_feerate(parent_fee, parent_vsize, child_fee, child_vsize);
+    let planning_target = fees::target_feerate(&*node.fee_estimator()).unwrap_or(FALLBACK_TARGET_FEERATE);
+    say!("🔮 Preview (sim): package feerate will be {}", feerate(package_rate));
+    say!("   ├─ Child fee needed for {}: {} (ours: {})",
+             feerate(planning_target), sats(sim::cpfp_child_fee(parent_vsize, parent_fee, child_vsize, planning_target)), sats(child_fee));
+    say!("   └─ Had the parent signalled RBF, {} would reach {}\n",
+             sats(sim::rbf_replacement_fee(parent_fee, parent_vsize, package_rate, node.context.incremental_fee)), feerate(package_rate));
 
-    // Create and sign child transaction
-    let child_raw = rpc.create_raw_transaction(&child_inputs, &child_outputs, None, None)?;
-    let child_signed = rpc.sign_raw_transaction_with_wallet(&child_raw, None, None)?;
+    // A relative timelock can't be satisfied while the parent is unconfirmed
+    if let Some(lock) = &csv_lock {
+        say!("🔒 Checking the child against its {}-block relative timelock (testmempoolaccept):", lock.blocks);
+        node.mine_until_final(&child_signed.transaction()?, lock.blocks, &funding_addr)?;
+        say!("💡 The parent had to CONFIRM first - CSV-locked outputs can't be used to CPFP a stuck parent!\n");
+    }
 
     // Broadcast child transaction
-    let child_txid = rpc.send_raw_transaction(&child_signed.hex)?;
-    println!("✅ Child TX broadcasted: {}", child_txid);
-    println!("   ├─ Spends: Parent output ({}:0)", parent_txid);
-    println!("   ├─ Output: {} BTC to final address", child_send_amount);
-    println!("   └─ Fee: {} BTC (HIGH!)", child_fee);
+    let child_txid = node.broadcast(&child_signed.hex, None)?;
+    say!("✅ Child TX broadcasted: {}", child_txid);
+    say!("   ├─ Spends: Parent output ({}:0)", parent_txid);
+    say!("   ├─ Output: {} to final address", btc(child_send_amount));
+    say!("   ├─ Fee: {} (HIGH!)", btc(child_fee));
+    say!("   └─ Size: {}", sim::size_breakdown(&child_signed.transaction()?));
+    node.verify_fee(child_txid, child_fee)?;
+
+    let mut package = vec![
+        (parent_txid, parent_signed.transaction()?, parent_fee),
+        (child_txid, child_signed.transaction()?, child_fee),
+    ];
+
+    // With --replaceable-child the child itself can be fee-bumped again
+    let (child_txid, child_signed) = if opts.replaceable_child {
+        let bumped_fee = node.min_replacement_fee(child_txid)? + rbf::REPLACEMENT_MARGIN;
+        node.confirm_mainnet_fees(&[("Replacement child fee", bumped_fee)])?;
+        say!("\n🔁 The child signals RBF (sequence {:#010x}): replacing it with a higher fee", child_sequence.0);
+        say!("   Press Enter to replace the CHILD...");
+        opts.prompt()?;
+
+        let before = rpc.get_raw_mempool()?;
+        let bumped_signed = build_child(bumped_fee)?;
+        let bumped_txid = node.broadcast(&bumped_signed.hex, None)?;
+        let delta = mempool_delta(&before, &rpc.get_raw_mempool()?);
+        say!("✅ Replacement child broadcasted: {}", bumped_txid);
+        say!("   ├─ Fee: {} → {}", btc(child_fee), btc(bumped_fee));
+        say!("   └─ Size: {}", sim::size_breakdown(&bumped_signed.transaction()?));
+        node.verify_fee(bumped_txid, bumped_fee)?;
+        say!("🔍 Mempool delta (the parent is untouched):");
+        say!("{}", delta);
+        ensure!(delta.removed == [child_txid], "replacing child {} evicted {:?}", child_txid, delta.removed);
+        say!("💡 RBF on the child, CPFP on the parent: the new child still pulls the parent along");
+
+        package.push((bumped_txid, bumped_signed.transaction()?, bumped_fee));
+        (bumped_txid, bumped_signed)
+    } else {
+        (child_txid, child_signed)
+    };
+
+    // With --cpfp-depth the child gets children of its own, up to the chain limits
+    if opts.cpfp_depth > 1 {
+        say!("\n⛓️  Extending the package to {} generations of children ({} fee each):", opts.cpfp_depth, btc(CHAIN_FEE_EACH));
+        let chain = node.build_cpfp_chain(OutPoint::new(child_txid, 0), opts.cpfp_depth - 1, CHAIN_FEE_EACH, &final_addr)?;
+        for txid in &chain {
+            package.push((*txid, rpc.get_raw_transaction(txid, None)?, node.mempool_fee(*txid)?));
+        }
+        if let Some(last) = chain.last() {
+            let entry = rpc.get_mempool_entry(last)?;
+            say!("   ├─ Deepest descendant's ancestor package: {} txs, {}", entry.ancestor_count, feerate(ancestor_feerate(&entry)));
+        }
+        say!("   └─ {} of {} extra generation(s) accepted", chain.len(), opts.cpfp_depth - 1);
+        if chain.len() < opts.cpfp_depth - 1 {
+            say!("💡 Every generation raises the package feerate, but the mempool caps how long a chain may get");
+        }
+    }
+
+    dot::write_dot(opts, &package)?;
+    dump::write_txs(opts, &package)?;
 
     // Check mempool after child
-    println!("\n🔍 Mempool Status (After CPFP):");
     let final_mempool = rpc.get_raw_mempool()?;
-    println!("   ├─ Total transactions: {}", final_mempool.len());
-    println!("   ├─ Parent TX present: {}", if final_mempool.contains(&parent_txid) { "✅ YES" } else { "❌ NO" });
-    println!("   └─ Child TX present: {}", if final_mempool.contains(&child_txid) { "✅ YES" } else { "❌ NO" });
+    say!("\n🔍 Mempool Status (After CPFP): {} transactions", final_mempool.len());
+    say!("{}", mempool_delta(&before_parent, &final_mempool));
 
-    // Show CPFP economics
-    println!("\n💰 CPFP Economics:");
-    println!("   ├─ Parent fee: {} BTC", parent_fee);
-    println!("   ├─ Child fee: {} BTC", child_fee);
-    println!("   ├─ Combined fee: {} BTC", parent_fee + child_fee);
-    println!("   └─ Miners see: HIGH total fee for transaction package!");
+    // Show CPFP economics as the node sees them (a CSV parent has already confirmed)
+    let parent_mempool_fee = if final_mempool.contains(&parent_txid) { node.mempool_fee(parent_txid)? } else { parent_fee };
+    let child_mempool_fee = node.mempool_fee(child_txid)?;
+    let view = sim::miner_view(parent_mempool_fee, parent_vsize, child_mempool_fee, child_vsize);
+    say!("\n💰 CPFP as a miner sees it:");
+    say!("   ├─ Parent alone: {} / {} vB = {}", btc(parent_mempool_fee), parent_vsize, feerate(view.parent_alone_feerate));
+    say!("   ├─ Child alone: {} / {} vB = {} (can't be mined without the parent)", btc(child_mempool_fee), child_vsize, feerate(view.child_alone_feerate));
+    say!("   ├─ Package: {} / {} vB = {}",
+             btc(parent_mempool_fee + child_mempool_fee), parent_vsize + child_vsize, feerate(view.package_feerate));
+    say!("   └─ Miners select by ancestor feerate: the package goes in when {} beats the marginal transaction's feerate, not because the fees add up", feerate(view.package_feerate));
+    if final_mempool.contains(&parent_txid) {
+        let (parent_tx, child_tx) = (parent_signed.transaction()?, child_signed.transaction()?);
+        say!("🧬 Admitted by: {}", node.cpfp_mechanism(&parent_tx, &child_tx)?);
+        say!("   └─ Carve-out eligible: {}", if carve_out_eligible(&parent_tx, &child_tx) { "✅ YES" } else { "❌ NO" });
+    }
+    node.print_rank("package", view.package_feerate)?;
 
     if final_mempool.contains(&parent_txid) && final_mempool.contains(&child_txid) {
-        println!("\n🎉 CPFP SUCCESS!");
-        println!("✅ Both parent and child are in mempool!");
-        println!("✅ High child fee incentivizes miners to include both!");
-        println!("✅ Parent gets 'pulled along' by profitable child!");
+        say!("\n🎉 CPFP SUCCESS!");
+        say!("✅ Both parent and child are in mempool!");
+        say!("✅ High child fee incentivizes miners to include both!");
+        say!("✅ Parent gets 'pulled along' by profitable child!");
     }
 
     // Mine a block to see final result
-    println!("\n⏸️  [FINAL DEMONSTRATION]");
-    println!("🔗 Let's mine a block to see both transactions get confirmed...");
-    println!("   Press Enter to mine block...");
-    input.clear();
-    std::io::stdin().read_line(&mut input)?;
-
-    println!("⛏️  Mining block...");
-    let blocks = rpc.generate_to_address(1, &funding_addr)?;
+    story.advance()?;
+
+    // Off regtest the real competition is the network's mempool: wait for a miner
+    let (competitors, block_hash) = if node.context.chain == Network::Regtest {
+        // Competitors sit between the parent's own feerate and the package feerate
+        let competitor_rate = (sim::feerate(parent_fee, parent_vsize) + package_rate) / 2.0;
+        let competitors = node.broadcast_competitors(COMPETITOR_COUNT, competitor_rate, &funding_addr)?;
+        say!("🥊 Broadcast {} competing transactions at ~{}", competitors.len(), feerate(competitor_rate));
+        say!("   ├─ Parent alone ({}) loses to them: {}", feerate(view.parent_alone_feerate), if view.parent_alone_feerate > competitor_rate { "❌ NO" } else { "✅ YES" });
+        say!("   └─ Package ({}) beats them: {}", feerate(view.package_feerate), if view.beats(competitor_rate) { "✅ YES" } else { "❌ NO" });
+
+        // Room for the package and just one competitor
+        let mempool_entries = rpc.get_raw_mempool_verbose()?;
+        let package_vsize: u64 = [parent_txid, child_txid].iter().filter_map(|t| mempool_entries.get(t)).map(|e| e.vsize).sum();
+        let max_vsize = package_vsize + mempool_entries[&competitors[0]].vsize;
+
+        say!("⛏️  Mining a block limited to {} vB (generateblock, best ancestor feerate first)...", max_vsize);
+        let block_hash = node.mine_feerate_limited_block(max_vsize, &funding_addr)?;
+        (competitors, block_hash)
+    } else {
+        (Vec::new(), node.confirm(node.confirmer(&funding_addr), &[child_txid]).await?)
+    };
     
     // Check what actually got confirmed
-    let block = rpc.get_block(&blocks[0])?;
-    println!("\n📦 Block {} mined!", blocks[0]);
-    println!("   ├─ Transactions in block: {}", block.txdata.len());
+    let block = rpc.get_block(&block_hash)?;
+    let block_height = rpc.get_block_header_info(&block_hash)?.height as u64;
+    say!("\n📦 Block {} mined!", block_hash);
+    say!("   ├─ Transactions in block: {}", block.txdata.len());
     
-    let parent_confirmed = block.txdata.iter().any(|tx| tx.compute_txid().to_string() == parent_txid.to_string());
-    let child_confirmed = block.txdata.iter().any(|tx| tx.compute_txid().to_string() == child_txid.to_string());
+    let mined: Vec<Txid> = block.txdata.iter().map(|tx| tx.compute_txid()).collect();
+    let parent_confirmed = mined.contains(&parent_txid);
+    let child_confirmed = mined.contains(&child_txid);
+    let competitors_mined = competitors.iter().filter(|c| mined.contains(c)).count();
+    let beat_competition = child_confirmed && competitors_mined < competitors.len();
     
-    println!("   ├─ Parent confirmed: {}", if parent_confirmed { "✅ YES" } else { "❌ NO" });
-    println!("   └─ Child confirmed: {}", if child_confirmed { "✅ YES" } else { "❌ NO" });
+    say!("   ├─ Parent confirmed: {}", if parent_confirmed { "✅ YES" } else { "❌ NO" });
+    say!("   ├─ Child confirmed: {}", if child_confirmed { "✅ YES" } else { "❌ NO" });
+    say!("   └─ Competitors confirmed: {}/{}", competitors_mined, competitors.len());
+    if parent_confirmed && child_confirmed {
+        node.print_block_stats(block_hash, view.package_feerate, 2);
+    }
+    if beat_competition {
+        say!("🥇 The package beat {} competitor(s) that outbid the parent alone: it was picked for the CHILD's fee!",
+                 competitors.len() - competitors_mined);
+    }
 
     // Final verdict
-    println!("\n🎉 CPFP DEMO COMPLETE!");
+    say!("\n🎉 CPFP DEMO COMPLETE!");
     if parent_confirmed && child_confirmed {
-        println!("🏆 PERFECT! Both parent and child were mined together!");
-        println!("💡 The high-fee child pulled the low-fee parent along!");
-        println!("💡 This is how CPFP accelerates stuck transactions!");
+        say!("🏆 PERFECT! Both parent and child were mined together!");
+        say!("💡 The high-fee child pulled the low-fee parent along!");
+        say!("💡 This is how CPFP accelerates stuck transactions!");
+    } else if child_confirmed && parent_already_confirmed {
+        say!("📤 Child mined as a normal spend: its parent had confirmed before the child existed");
+    } else if child_confirmed && csv_lock.is_some() {
+        say!("🔒 Child mined on its own: the CSV lock forced the parent to confirm first");
+        say!("💡 Relative timelocks and CPFP don't mix - that's why anchor outputs are unencumbered");
     } else if child_confirmed && !parent_confirmed {
-        println!("🤔 Only child was mined - this shouldn't happen!");
-        println!("   (Child can't be valid without parent)");
+        say!("🤔 Only child was mined - this shouldn't happen!");
+        say!("   (Child can't be valid without parent)");
+    } else {
+        say!("🤷 Neither transaction was mined - check the implementation");
+    }
+
+    say!("\n📚 What we demonstrated:");
+    say!("   ├─ Created parent transaction with very low fee");
+    say!("   ├─ Parent got stuck (no RBF available)");
+    say!("   ├─ Created child spending from parent with very high fee");
+    say!("   ├─ Miners included both transactions for their package feerate");
+    say!("   └─ Child 'paid for' parent's confirmation");
+
+    say!("\n💡 Key CPFP Insights:");
+    say!("   ├─ Child transaction MUST spend parent's output");
+    say!("   ├─ Miners consider package fee rate (total fees / total size)");
+    say!("   ├─ High child fee can make low parent fee profitable");
+    say!("   ├─ Both transactions are mined together (atomic)");
+    say!("   └─ Useful when RBF is not available or desired");
+
+    Ok(Some(CpfpOutcome {
+        parent_txid,
+        child_txid,
+        parent_fee: parent_mempool_fee,
+        child_fee: child_mempool_fee,
+        parent_feerate: view.parent_alone_feerate,
+        package_feerate: view.package_feerate,
+        block: (block_hash, block_height),
+        parent_confirmed,
+        child_confirmed,
+        beat_competition,
+    }))
+}
+
+impl Node {
+    /// A child spending output `vout` of `parent` to this run's change address,
+    /// paying enough for the package to reach `target` sat/vB. Unsigned, with
+    /// the fee it pays.
+    pub fn unsigned_child(&self, parent: &Transaction, vout: usize, fee: Amount, target: f64) -> Result<(Transaction, Amount)> {
+        let value = parent.output.get(vout).ok_or_else(|| anyhow!("{} has no output {}", parent.compute_txid(), vout))?.value;
+        let child_fee = sim::cpfp_child_fee(parent.vsize(), fee, sim::TYPICAL_CHILD_VSIZE, target);
+        let rest = value
+            .checked_sub(child_fee)
+            .ok_or_else(|| anyhow!("output {} ({}) can't pay a {} child fee", vout, btc(value), btc(child_fee)))?;
+        say!("👶 CPFP child: spends output {} ({}), pays {} in fees", vout, btc(value), btc(child_fee));
+        let child = self.tx_builder()?
+            .input(OutPoint::new(parent.compute_txid(), vout as u32), SEQUENCE_RBF)
+            .pay(rest, self.change_address()?)
+            .build();
+        Ok((child, child_fee))
+    }
+
+    /// Builds, signs and broadcasts a child spending `target` that leaves
+    /// `child_fee` behind for the miner.
+    pub fn cpfp_child(&self, target: OutPoint, child_fee: Amount, dest: &Address) -> Result<Txid> {
+        let prevout = self.rpc.get_tx_out(&target.txid, target.vout, Some(true))?
+            .ok_or_else(|| anyhow!("{} is not an unspent output", target))?;
+        let send_amount = prevout.value.checked_sub(child_fee)
+            .ok_or_else(|| anyhow!("{} ({}) cannot cover a {} child fee", target, btc(prevout.value), btc(child_fee)))?;
+
+        let tx = self.tx_builder()?
+            .input(target, SEQUENCE_NO_RBF)
+            .pay(send_amount, dest)
+            .build();
+        let signed = self.sign_with_wallet(&tx)?;
+        self.broadcast(&signed.hex, None)
+    }
+
+    /// One child spending every output `vouts` of `parent` at once, leaving
+    /// `extra_fee` for the miner. Each output must be unspent and the wallet's.
+    pub fn bump_multiple_outputs(&self, parent: Txid, vouts: &[u32], extra_fee: Amount, dest: &Address) -> Result<Txid> {
+        ensure!(!vouts.is_empty(), "no outputs of {} to spend", parent);
+        let mut child = self.tx_builder()?;
+        let mut total = Amount::ZERO;
+        for &vout in vouts {
+            let prevout = self.rpc.get_tx_out(&parent, vout, Some(true))?
+                .ok_or_else(|| anyhow!("{}:{} is not an unspent output", parent, vout))?;
+            let script = prevout.script_pub_key.script()?;
+            ensure!(self.is_mine(&script)?, "{}:{} does not belong to wallet '{}'", parent, vout, self.opts.wallet);
+            child = child.input(OutPoint::new(parent, vout), SEQUENCE_NO_RBF);
+            total += prevout.value;
+        }
+        let send_amount = total.checked_sub(extra_fee)
+            .ok_or_else(|| anyhow!("{} output(s) of {} ({}) cannot cover a {} child fee", vouts.len(), parent, btc(total), btc(extra_fee)))?;
+
+        let signed = self.sign_with_wallet(&child.pay(send_amount, dest).build())?;
+        self.broadcast(&signed.hex, None)
+    }
+}
+
+/// Accelerates every stuck output in `targets` with its own CPFP child. The
+/// blocking RPC work for each target runs concurrently on tokio's blocking pool.
+pub async fn accelerate_batch(node: Arc<Node>, targets: Vec<OutPoint>, child_fee: Amount, dest: Address) -> Result<Vec<Txid>> {
+    let tasks: Vec<_> = targets
+        .into_iter()
+        .map(|target| {
+            let node = Arc::clone(&node);
+            let dest = dest.clone();
+            tokio::task::spawn_blocking(move || node.cpfp_child(target, child_fee, &dest))
+        })
+        .collect();
+
+    let mut child_txids = Vec::with_capacity(tasks.len());
+    for task in tasks {
+        child_txids.push(task.await??);
+    }
+    Ok(child_txids)
+}
+
+pub async fn run_batch_demo(opts: &Options) -> Result<()> {
+    say!("🚀 Batch CPFP Demo - Accelerating several parents at once\n");
+    let mut story = Story::new(vec![
+        DemoStep::step(1, "Creating several PARENT transactions with LOW fee"),
+        DemoStep::step(2, "Spawning one CPFP child per parent, concurrently"),
+        DemoStep::step(3, "A parent paying the wallet TWICE (payment to self + change), one child spending both"),
+    ], opts.interactive);
+
+    let node = Arc::new(Node::connect(opts)?);
+    let rpc = &node.rpc;
+
+    // Get addresses
+    let funding_addr = rpc.get_new_address(None, None)?.assume_checked();
+    let intermediate_addr = rpc.get_new_address(None, None)?.assume_checked();
+    let final_addr = rpc.get_new_address(None, None)?.assume_checked();
+
+    // Fund wallet if needed, with one large UTXO per stuck parent
+    let unspent = node.fund_utxos_if_needed(&funding_addr, BATCH_PARENTS, Amount::ONE_BTC)?;
+    let utxos: Vec<_> = unspent.iter().take(BATCH_PARENTS).collect();
+
+    // === STEP 1: Create several stuck parents ===
+    story.advance()?;
+    let parent_fee = Amount::from_sat(10_000);
+    let child_fee = Amount::from_sat(1_000_000);
+    // A fourth large UTXO, if any, funds a parent with two wallet outputs (STEP 3)
+    let split_utxo = unspent.get(utxos.len());
+    let parents = (utxos.len() + usize::from(split_utxo.is_some())) as u64;
+    node.confirm_mainnet_fees(&[("Parent fees", parent_fee * parents), ("Child fees", child_fee * parents)])?;
+    let mut targets = Vec::new();
+    for utxo in &utxos {
+        node.lock_utxo(OutPoint::new(utxo.txid, utxo.vout))?;
+        let tx = node.tx_builder()?
+            .input(OutPoint::new(utxo.txid, utxo.vout), SEQUENCE_FINAL) // NO RBF - final sequence
+            .pay(utxo.amount - parent_fee, &intermediate_addr)
+            .build();
+        let signed = node.sign_with_wallet(&tx)?;
+        let parent_txid = node.broadcast(&signed.hex, None)?;
+        say!("   ├─ Parent: {} (fee: {})", parent_txid, btc(parent_fee));
+        targets.push(OutPoint::new(parent_txid, 0));
+    }
+    say!("   └─ All parents stuck with very low fees\n");
+
+    // === STEP 2: Accelerate them all concurrently ===
+    story.advance()?;
+    say!("   ├─ Children: {}", targets.len());
+    say!("   └─ Fee per child: {}\n", btc(child_fee));
+
+    let child_txids = accelerate_batch(Arc::clone(&node), targets.clone(), child_fee, final_addr.clone()).await?;
+    for (target, child_txid) in targets.iter().zip(&child_txids) {
+        say!("✅ {} accelerated by child {}", target.txid, child_txid);
+    }
+
+    // === STEP 3: One child for a parent paying the wallet twice ===
+    let mut split = None;
+    if let Some(utxo) = split_utxo {
+        story.advance()?;
+        node.lock_utxo(OutPoint::new(utxo.txid, utxo.vout))?;
+        let half = (utxo.amount - parent_fee) / 2;
+        let tx = node.tx_builder()?
+            .input(OutPoint::new(utxo.txid, utxo.vout), SEQUENCE_FINAL)
+            .pay(half, &intermediate_addr)
+            .pay(utxo.amount - parent_fee - half, node.change_address()?)
+            .build();
+        let parent_txid = node.broadcast(&node.sign_with_wallet(&tx)?.hex, None)?;
+        let child_txid = node.bump_multiple_outputs(parent_txid, &[0, 1], child_fee, &final_addr)?;
+        say!("   ├─ Parent: {} (fee: {}, 2 wallet outputs)", parent_txid, btc(parent_fee));
+        say!("   └─ ✅ Accelerated by child {} spending {}:0 and {}:1", child_txid, parent_txid, parent_txid);
+        split = Some((parent_txid, child_txid));
     } else {
-        println!("🤷 Neither transaction was mined - check the implementation");
+        story.skip();
     }
 
-    println!("\n📚 What we demonstrated:");
-    println!("   ├─ Created parent transaction with very low fee");
-    println!("   ├─ Parent got stuck (no RBF available)");
-    println!("   ├─ Created child spending from parent with very high fee");
-    println!("   ├─ Miners included both transactions for the combined fee");
-    println!("   └─ Child 'paid for' parent's confirmation");
+    // Mine a block to see final result
+    let mut txids = child_txids.clone();
+    txids.extend(split.map(|(_, child)| child));
+    let block_hash = node.confirm(node.confirmer(&funding_addr), &txids).await?;
+    let block = rpc.get_block(&block_hash)?;
+    let mined: Vec<Txid> = block.txdata.iter().map(|tx| tx.compute_txid()).collect();
+    let all_confirmed = targets.iter().all(|t| mined.contains(&t.txid))
+        && child_txids.iter().all(|c| mined.contains(c))
+        && split.is_none_or(|(parent, child)| mined.contains(&parent) && mined.contains(&child));
 
-    println!("\n💡 Key CPFP Insights:");
-    println!("   ├─ Child transaction MUST spend parent's output");
-    println!("   ├─ Miners consider package fee rate (total fees / total size)");
-    println!("   ├─ High child fee can make low parent fee profitable");
-    println!("   ├─ Both transactions are mined together (atomic)");
-    println!("   └─ Useful when RBF is not available or desired");
+    say!("\n📦 Block {} mined!", block_hash);
+    say!("   ├─ Transactions in block: {}", block.txdata.len());
+    say!("   └─ All parents and children confirmed: {}", if all_confirmed { "✅ YES" } else { "❌ NO" });
 
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,168 @@
+//! `accelerate <txid:vout>`: goal-oriented acceleration, the way wallets expose
+//! a "priority" slider. Ask for a confirmation deadline in blocks; the feerate
+//! comes from `estimatesmartfee` and the fee from the chosen method's own rules.
+//! [`Node::estimate_acceleration_cost`] plans the same thing without sending
+//! anything, for the demos' previews.
+
+use anyhow::{anyhow, bail, ensure, Result};
+use bitcoin::{Amount, OutPoint, Txid};
+use bitcoincore_rpc::RpcApi;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::consts::ANCHOR_INPUT_VSIZE;
+use crate::fees;
+use crate::fmt::{btc, feerate};
+use crate::node::Node;
+use crate::options::Options;
+use crate::p2a::is_p2a;
+use crate::sim;
+
+/// Confirmation targets (blocks) a cost estimate is matched against, soonest first.
+const ESTIMATE_TARGETS: [u16; 7] = [1, 2, 3, 6, 12, 24, 144];
+
+/// How to accelerate the transaction behind a deadline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    /// Replace it, taking the higher fee out of the target output.
+    Rbf,
+    /// Spend the target output with a high-fee child.
+    Cpfp,
+    /// Spend the target P2A anchor with a v3 child funded by a wallet UTXO.
+    P2a,
+}
+
+impl FromStr for Method {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "rbf" => Ok(Method::Rbf),
+            "cpfp" => Ok(Method::Cpfp),
+            "p2a" => Ok(Method::P2a),
+            other => bail!("unknown method '{}': expected rbf, cpfp or p2a", other),
+        }
+    }
+}
+
+impl fmt::Display for Method {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Method::Rbf => "RBF",
+            Method::Cpfp => "CPFP",
+            Method::P2a => "P2A",
+        })
+    }
+}
+
+/// What an acceleration would cost, worked out before anything is signed or sent.
+pub struct CostEstimate {
+    pub method: Method,
+    /// Fee on top of what the stuck transaction already pays.
+    pub extra_fee: Amount,
+    /// vbytes the acceleration adds: the child for CPFP and P2A, none for a
+    /// same-shape RBF replacement.
+    pub added_vsize: usize,
+    /// sat/vB a miner would see: the replacement's, or the package's.
+    pub effective_feerate: f64,
+    /// Soonest of [`ESTIMATE_TARGETS`] that feerate should confirm within,
+    /// `None` without fee estimates or if even 144 blocks needs more.
+    pub conf_target: Option<u16>,
+}
+
+impl fmt::Display for CostEstimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "🔮 {} preview (nothing broadcast yet):", self.method)?;
+        writeln!(f, "   ├─ Extra fee: {}", btc(self.extra_fee))?;
+        writeln!(f, "   ├─ Added size: {} vB", self.added_vsize)?;
+        writeln!(f, "   ├─ Effective feerate: {}", feerate(self.effective_feerate))?;
+        match self.conf_target {
+            Some(blocks) => write!(f, "   └─ Expected to confirm within: {} block(s)", blocks),
+            None => write!(f, "   └─ Expected to confirm within: unknown (no matching fee estimate)"),
+        }
+    }
+}
+
+impl Node {
+    /// Plans accelerating the mempool transaction `target` belongs to with
+    /// `method` on output `target.vout` (as in [`Self::accelerate_for_deadline`])
+    /// at `target_feerate` sat/vB. Read-only: the fee comes from [`sim`] on the
+    /// stuck transaction's real size and a typical child, and the confirmation
+    /// target from the fee estimator.
+    pub fn estimate_acceleration_cost(&self, target: OutPoint, method: Method, target_feerate: f64) -> Result<CostEstimate> {
+        let stuck = self.rpc.get_raw_transaction(&target.txid, None)?;
+        let fee = self.fee_of(&stuck)?;
+        let output = stuck.output.get(target.vout as usize)
+            .ok_or_else(|| anyhow!("{} has no output {}", target.txid, target.vout))?;
+
+        let (extra_fee, added_vsize, effective_feerate) = match method {
+            Method::Rbf => {
+                let new_fee = sim::rbf_replacement_fee(fee, stuck.vsize(), target_feerate, self.context.incremental_fee);
+                let bump = new_fee - fee;
+                ensure!(
+                    output.value.checked_sub(bump).is_some_and(|value| value >= output.script_pubkey.minimal_non_dust()),
+                    "output {} ({}) can't cover a {} bump", target.vout, btc(output.value), btc(bump)
+                );
+                (bump, 0, sim::feerate(new_fee, stuck.vsize()))
+            }
+            Method::Cpfp | Method::P2a => {
+                let child_vsize = match method {
+                    Method::P2a => {
+                        ensure!(is_p2a(&output.script_pubkey), "output {} of {} is not a P2A anchor", target.vout, target.txid);
+                        sim::TYPICAL_CHILD_VSIZE + ANCHOR_INPUT_VSIZE
+                    }
+                    _ => sim::TYPICAL_CHILD_VSIZE,
+                };
+                let child_fee = sim::cpfp_child_fee(stuck.vsize(), fee, child_vsize, target_feerate);
+                (child_fee, child_vsize, sim::package_feerate(fee, stuck.vsize(), child_fee, child_vsize))
+            }
+        };
+
+        let estimator = self.fee_estimator();
+        let conf_target = ESTIMATE_TARGETS.into_iter()
+            .find(|blocks| estimator.estimate(*blocks).is_ok_and(|rate| rate <= effective_feerate));
+        Ok(CostEstimate { method, extra_fee, added_vsize, effective_feerate, conf_target })
+    }
+
+    /// Prints [`Self::estimate_acceleration_cost`] at the demos' target feerate,
+    /// or why there is no preview.
+    pub fn print_cost_preview(&self, target: OutPoint, method: Method) -> Result<()> {
+        let Some(rate) = fees::target_feerate(&*self.fee_estimator()) else {
+            say!("🔮 No {} cost preview without a target feerate\n", method);
+            return Ok(());
+        };
+        say!("{}\n", self.estimate_acceleration_cost(target, method, rate)?);
+        Ok(())
+    }
+
+    /// Accelerates the mempool transaction `target` belongs to so it should
+    /// confirm within `blocks`, using `method` on output `target.vout`: the
+    /// output RBF takes the bump from, CPFP spends, or the P2A anchor.
+    pub fn accelerate_for_deadline(&self, target: OutPoint, blocks: u16, method: Method) -> Result<Txid> {
+        let rate = self.deadline_feerate(blocks)?;
+        let stuck = self.rpc.get_raw_transaction(&target.txid, None)?;
+        let fee = self.fee_of(&stuck)?;
+        say!("🎯 {} pays {}, needs {} to confirm within {} blocks",
+                 target.txid, feerate(sim::feerate(fee, stuck.vsize())), feerate(rate), blocks);
+
+        let (tx, new_fee) = match method {
+            Method::Rbf => self.unsigned_replacement(&stuck, target.vout as usize, fee, rate)?,
+            Method::Cpfp => self.unsigned_child(&stuck, target.vout as usize, fee, rate)?,
+            Method::P2a => self.unsigned_anchor_spend(&stuck, target.vout, fee, rate)?,
+        };
+        self.confirm_mainnet_fees(&[(&format!("{} fee", method), new_fee)])?;
+        let signed = self.sign_with_wallet(&tx)?.transaction()?;
+        let txid = match method {
+            Method::P2a => self.broadcast_with_burn_limit(&signed)?,
+            Method::Rbf | Method::Cpfp => self.broadcast(&signed, None)?,
+        };
+        say!("✅ {} broadcast: {} (fee {})", method, txid, btc(new_fee));
+        Ok(txid)
+    }
+}
+
+/// Runs `accelerate <txid:vout>` with the `--deadline` and `--method` options.
+pub fn run(opts: &Options, target: OutPoint) -> Result<()> {
+    Node::connect(opts)?.accelerate_for_deadline(target, opts.deadline, opts.method)?;
+    Ok(())
+}
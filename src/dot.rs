@@ -0,0 +1,88 @@
+//! Graphviz rendering of the transactions a demo built, so the package can be
+//! drawn (`dot -Tpng package.dot -o package.png`).
+
+use anyhow::Result;
+use bitcoin::{Amount, Transaction, Txid};
+use std::fmt::Write;
+
+use crate::fmt::feerate;
+use crate::options::Options;
+use crate::sim;
+
+/// DOT digraph of `txs`: one node per transaction labelled with its txid prefix
+/// and feerate, a solid edge for every output one of them spends from another,
+/// and a dashed edge from a transaction to a later one that double-spends it.
+pub fn package_to_dot(txs: &[(Txid, Transaction, Amount)]) -> String {
+    let mut dot = String::from("digraph package {\n    rankdir=LR;\n    node [shape=box, fontname=monospace];\n");
+
+    for (txid, tx, fee) in txs {
+        let _ = writeln!(
+            dot,
+            "    \"{}\" [label=\"{:.8}…\\n{}\"];",
+            txid, txid.to_string(), feerate(sim::feerate(*fee, tx.vsize()))
+        );
+    }
+
+    for (i, (txid, tx, _)) in txs.iter().enumerate() {
+        for input in &tx.input {
+            let prevout = input.previous_output;
+            if txs.iter().any(|(parent, _, _)| *parent == prevout.txid) {
+                let _ = writeln!(dot, "    \"{}\" -> \"{}\" [label=\"vout {}\"];", prevout.txid, txid, prevout.vout);
+            }
+        }
+        // One edge per replacement, however many inputs the two share
+        for (later, later_tx, _) in &txs[i + 1..] {
+            if tx.input.iter().any(|input| later_tx.input.iter().any(|other| other.previous_output == input.previous_output)) {
+                let _ = writeln!(dot, "    \"{}\" -> \"{}\" [style=dashed, label=\"replaced by\"];", txid, later);
+            }
+        }
+    }
+
+    dot.push_str("}\n");
+    dot
+}
+
+/// Writes [`package_to_dot`] to the `--dot` path, if one was given.
+pub fn write_dot(opts: &Options, txs: &[(Txid, Transaction, Amount)]) -> Result<()> {
+    if let Some(path) = &opts.dot {
+        std::fs::write(path, package_to_dot(txs))?;
+        say!("🖼️  Package diagram written to {}", path.display());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::absolute::LockTime;
+    use bitcoin::hashes::Hash;
+    use bitcoin::transaction::Version;
+    use bitcoin::{OutPoint, ScriptBuf};
+
+    use crate::builder::TxBuilder;
+    use crate::consts::SEQUENCE_FINAL;
+
+    fn spend(outpoints: &[OutPoint], value: u64) -> (Txid, Transaction, Amount) {
+        let tx = outpoints
+            .iter()
+            .fold(TxBuilder::new(Version::TWO, LockTime::ZERO), |tx, outpoint| tx.input(*outpoint, SEQUENCE_FINAL))
+            .output(Amount::from_sat(value), ScriptBuf::new())
+            .build();
+        (tx.compute_txid(), tx, Amount::from_sat(1_000))
+    }
+
+    #[test]
+    fn draws_labels_spends_and_one_replacement_edge() {
+        let coins = [OutPoint::new(Txid::all_zeros(), 0), OutPoint::new(Txid::all_zeros(), 1)];
+        let original = spend(&coins, 50_000);
+        let child = spend(&[OutPoint::new(original.0, 0)], 40_000);
+        let replacement = spend(&coins, 45_000);
+        let dot = package_to_dot(&[original.clone(), child.clone(), replacement.clone()]);
+
+        let label = format!("\"{}\" [label=\"{:.8}…", original.0, original.0.to_string());
+        assert!(dot.contains(&label), "{}", dot);
+        assert!(dot.contains(&format!("\"{}\" -> \"{}\" [label=\"vout 0\"]", original.0, child.0)), "{}", dot);
+        let replaced = format!("\"{}\" -> \"{}\" [style=dashed", original.0, replacement.0);
+        assert_eq!(dot.matches(&replaced).count(), 1, "{}", dot);
+    }
+}
@@ -0,0 +1,83 @@
+//! `--out-dir`: an archive of exactly what each demo broadcast, one `.hex`
+//! (ready for `sendrawtransaction` or `decoderawtransaction`) and one `.json`
+//! summary per transaction, named by txid.
+
+use anyhow::Result;
+use bitcoin::{Amount, Transaction, Txid};
+use serde::Serialize;
+use std::path::Path;
+
+use crate::fmt::btc;
+use crate::inspect::script_kind;
+use crate::options::Options;
+use crate::sim;
+
+#[derive(Serialize)]
+pub struct OutputSummary {
+    pub value_sat: u64,
+    /// `value_sat` as printed (`--unit`).
+    pub value: String,
+    pub kind: &'static str,
+    pub script_pubkey: String,
+}
+
+/// The decoded facts about a transaction worth keeping next to its hex.
+#[derive(Serialize)]
+pub struct TxSummary {
+    pub txid: String,
+    pub version: i32,
+    pub vsize: usize,
+    pub weight: u64,
+    pub fee_sat: u64,
+    /// `fee_sat` as printed (`--unit`).
+    pub fee: String,
+    pub feerate_sat_vb: f64,
+    pub signals_rbf: bool,
+    pub inputs: Vec<String>,
+    pub outputs: Vec<OutputSummary>,
+}
+
+impl TxSummary {
+    pub fn new(tx: &Transaction, fee: Amount) -> Self {
+        TxSummary {
+            txid: tx.compute_txid().to_string(),
+            version: tx.version.0,
+            vsize: tx.vsize(),
+            weight: tx.weight().to_wu(),
+            fee_sat: fee.to_sat(),
+            fee: btc(fee),
+            feerate_sat_vb: sim::feerate(fee, tx.vsize()),
+            signals_rbf: tx.is_explicitly_rbf(),
+            inputs: tx.input.iter().map(|input| input.previous_output.to_string()).collect(),
+            outputs: tx
+                .output
+                .iter()
+                .map(|output| OutputSummary {
+                    value_sat: output.value.to_sat(),
+                    value: btc(output.value),
+                    kind: script_kind(&output.script_pubkey),
+                    script_pubkey: output.script_pubkey.to_hex_string(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Writes `<txid>.hex` and `<txid>.json` into `dir`, creating it if needed.
+pub fn dump_tx(dir: &Path, txid: Txid, hex: &str, summary: &TxSummary) -> Result<()> {
+    std::fs::create_dir_all(dir)?;
+    std::fs::write(dir.join(format!("{}.hex", txid)), format!("{}\n", hex))?;
+    std::fs::write(dir.join(format!("{}.json", txid)), serde_json::to_string_pretty(summary)? + "\n")?;
+    Ok(())
+}
+
+/// Dumps every transaction in `txs` into the `--out-dir`, if one was given.
+pub fn write_txs(opts: &Options, txs: &[(Txid, Transaction, Amount)]) -> Result<()> {
+    if let Some(dir) = &opts.out_dir {
+        for (txid, tx, fee) in txs {
+            dump_tx(dir, *txid, &bitcoin::consensus::encode::serialize_hex(tx), &TxSummary::new(tx, *fee))?;
+        }
+        say!("🗄️  {} signed transaction(s) written to {}", txs.len(), dir.display());
+    }
+    Ok(())
+}
@@ -0,0 +1,221 @@
+//! bitcoind's broadcast rejections, translated. Races between runs and
+//! mempool policy all surface as terse reject strings inside a JSON-RPC error;
+//! these say what happened and what to do about it.
+
+use anyhow::anyhow;
+use bitcoin::consensus::encode::deserialize_hex;
+use bitcoin::relative;
+use bitcoin::transaction::Version;
+use bitcoin::{Amount, Transaction, Txid};
+use bitcoincore_rpc::jsonrpc;
+use bitcoincore_rpc::{RawTx, RpcApi};
+use serde_json::json;
+use std::fmt;
+
+use crate::fmt::btc;
+use crate::node::Node;
+
+/// A known broadcast rejection, carrying bitcoind's own message.
+#[derive(Debug)]
+pub enum FriendlyError {
+    /// An input is unknown or already spent (by another run, or a replaced parent).
+    MissingInputs(String),
+    /// The node already has this exact transaction.
+    AlreadyKnown(String),
+    /// Below the relay/mempool minimum, or too little to replace a conflict (BIP125).
+    InsufficientFee(String),
+    /// Too many unconfirmed ancestors or descendants.
+    TooLongMempoolChain(String),
+    /// An absolute timelock (`nLockTime`) isn't satisfied yet.
+    NonFinal(String),
+    /// A relative timelock (BIP68 `nSequence`) isn't satisfied yet.
+    NonBip68Final(String),
+    /// OP_RETURN or anchor outputs carry more than `maxburnamount` allows.
+    MaxBurnExceeded(String),
+}
+
+impl fmt::Display for FriendlyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FriendlyError::MissingInputs(msg) => write!(
+                f,
+                "an input is missing or already spent ({}): another run may have spent the UTXO, or the parent was replaced or mined away; rerun to pick fresh UTXOs",
+                msg
+            ),
+            FriendlyError::AlreadyKnown(msg) => write!(f, "the node already has this transaction ({}): nothing to do", msg),
+            FriendlyError::InsufficientFee(msg) => write!(
+                f,
+                "fee too low ({}): raise the fee, or for a replacement pay the original's fee plus the incremental relay fee",
+                msg
+            ),
+            FriendlyError::TooLongMempoolChain(msg) => write!(
+                f,
+                "mempool chain limit hit ({}): mine a block or raise -limitancestorcount/-limitdescendantcount",
+                msg
+            ),
+            FriendlyError::NonFinal(msg) => write!(f, "timelock not yet satisfied ({}): mine more blocks and retry", msg),
+            FriendlyError::NonBip68Final(msg) => write!(
+                f,
+                "relative timelock not yet satisfied ({}): an input's coin must be buried deeper (its nSequence); mine more blocks and retry",
+                msg
+            ),
+            FriendlyError::MaxBurnExceeded(msg) => write!(f, "unspendable outputs exceed maxburnamount ({})", msg),
+        }
+    }
+}
+
+impl std::error::Error for FriendlyError {}
+
+/// bitcoind's own message for an RPC it answered with an error; `None` for
+/// transport and parsing failures.
+pub fn reject_reason(e: &bitcoincore_rpc::Error) -> Option<String> {
+    match e {
+        bitcoincore_rpc::Error::JsonRpc(jsonrpc::Error::Rpc(rpc)) => Some(rpc.message.clone()),
+        _ => None,
+    }
+}
+
+/// Recognizes the bitcoind rejections the demos can run into.
+pub fn classify_rpc_error(e: &bitcoincore_rpc::Error) -> Option<FriendlyError> {
+    let message = reject_reason(e)?;
+    let has = |needle: &str| message.contains(needle);
+    if has("missingorspent") || has("missing-inputs") || has("Missing inputs") {
+        Some(FriendlyError::MissingInputs(message))
+    } else if has("txn-already-known") || has("txn-already-in-mempool") || has("already in block chain") {
+        Some(FriendlyError::AlreadyKnown(message))
+    } else if has("insufficient fee") || has("min relay fee not met") || has("mempool min fee not met") {
+        Some(FriendlyError::InsufficientFee(message))
+    } else if has("too-long-mempool-chain") {
+        Some(FriendlyError::TooLongMempoolChain(message))
+    } else if has("non-BIP68-final") {
+        Some(FriendlyError::NonBip68Final(message))
+    } else if has("non-final") {
+        Some(FriendlyError::NonFinal(message))
+    } else if has("maxburnamount") {
+        Some(FriendlyError::MaxBurnExceeded(message))
+    } else {
+        None
+    }
+}
+
+/// `e` as a [`FriendlyError`] if it is a known rejection, as-is otherwise.
+pub fn translate_rpc_error(e: bitcoincore_rpc::Error) -> anyhow::Error {
+    match classify_rpc_error(&e) {
+        Some(friendly) => friendly.into(),
+        None => e.into(),
+    }
+}
+
+impl Node {
+    /// `sendrawtransaction`, with known rejections translated, and with
+    /// `maxburnamount` set when `max_burn` is given. A relative timelock
+    /// rejection also says how many blocks are left to wait.
+    pub fn broadcast<R: RawTx>(&self, tx: R, max_burn: Option<Amount>) -> anyhow::Result<Txid> {
+        let hex = tx.raw_hex();
+        let sent = match max_burn {
+            Some(burn) => self.rpc.call("sendrawtransaction", &[json!(hex), json!(null), json!(burn.to_btc())]),
+            None => self.rpc.send_raw_transaction(hex.as_str()),
+        };
+        let err = match sent {
+            Ok(txid) => return Ok(txid),
+            Err(e) => translate_rpc_error(e),
+        };
+        match err.downcast_ref() {
+            Some(FriendlyError::NonBip68Final(_)) => {
+                let tx: Transaction = deserialize_hex(&hex)?;
+                let blocks = self.blocks_until_final(&tx)?;
+                Err(err.context(format!("{} becomes valid in {} more block(s)", tx.compute_txid(), blocks)))
+            }
+            Some(FriendlyError::MaxBurnExceeded(_)) => Err(err.context(format!(
+                "the node counts more than the {} in OP_RETURN/anchor outputs as burned",
+                btc(max_burn.unwrap_or(Amount::ZERO))
+            ))),
+            _ => Err(err),
+        }
+    }
+
+    /// How many more blocks must be mined before every BIP68 relative timelock
+    /// in `tx` is satisfied, measured from each input's coin confirmation
+    /// height (the next block for a coin still in the mempool). 0 means the
+    /// next block could include it. Time-based locks (512-second units) count
+    /// one block per 10 minutes of median time still to pass.
+    pub fn blocks_until_final(&self, tx: &Transaction) -> anyhow::Result<u32> {
+        if tx.version < Version::TWO {
+            return Ok(0);
+        }
+        let tip = self.rpc.get_block_count()?;
+        let tip_mtp = self.rpc.get_blockchain_info()?.median_time;
+        let mut blocks = 0;
+        for input in &tx.input {
+            let Some(lock) = input.sequence.to_relative_lock_time() else { continue };
+            let prevout = input.previous_output;
+            let coin = self.rpc.get_tx_out(&prevout.txid, prevout.vout, Some(true))?
+                .ok_or_else(|| anyhow!("input {} is missing or already spent", prevout))?;
+            let coin_height = tip + 1 - u64::from(coin.confirmations);
+            let needed = match lock {
+                relative::LockTime::Blocks(height) => (coin_height + u64::from(height.value())).saturating_sub(tip + 1),
+                relative::LockTime::Time(time) => {
+                    // Measured from the median time past of the block before the coin's
+                    let coin_mtp = if coin.confirmations == 0 {
+                        tip_mtp
+                    } else {
+                        let hash = self.rpc.get_block_hash(coin_height.saturating_sub(1))?;
+                        self.rpc.get_block_header_info(&hash)?.median_time.unwrap_or_default() as u64
+                    };
+                    (coin_mtp + u64::from(time.value()) * 512).saturating_sub(tip_mtp).div_ceil(600)
+                }
+            };
+            blocks = blocks.max(needed as u32);
+        }
+        Ok(blocks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rejected(message: &str) -> bitcoincore_rpc::Error {
+        bitcoincore_rpc::Error::JsonRpc(jsonrpc::Error::Rpc(jsonrpc::error::RpcError {
+            code: -26,
+            message: message.to_string(),
+            data: None,
+        }))
+    }
+
+    fn variant(message: &str) -> Option<&'static str> {
+        classify_rpc_error(&rejected(message)).map(|e| match e {
+            FriendlyError::MissingInputs(_) => "MissingInputs",
+            FriendlyError::AlreadyKnown(_) => "AlreadyKnown",
+            FriendlyError::InsufficientFee(_) => "InsufficientFee",
+            FriendlyError::TooLongMempoolChain(_) => "TooLongMempoolChain",
+            FriendlyError::NonFinal(_) => "NonFinal",
+            FriendlyError::NonBip68Final(_) => "NonBip68Final",
+            FriendlyError::MaxBurnExceeded(_) => "MaxBurnExceeded",
+        })
+    }
+
+    #[test]
+    fn reject_strings_classified() {
+        let cases = [
+            ("bad-txns-inputs-missingorspent", "MissingInputs"),
+            ("missing-inputs", "MissingInputs"),
+            ("txn-already-known", "AlreadyKnown"),
+            ("txn-already-in-mempool", "AlreadyKnown"),
+            ("Transaction already in block chain", "AlreadyKnown"),
+            ("insufficient fee, rejecting replacement", "InsufficientFee"),
+            ("min relay fee not met, 100 < 110", "InsufficientFee"),
+            ("mempool min fee not met", "InsufficientFee"),
+            ("too-long-mempool-chain, too many descendants", "TooLongMempoolChain"),
+            ("non-BIP68-final", "NonBip68Final"),
+            ("non-final", "NonFinal"),
+            ("Unspendable output exceeds maximum configured by user (maxburnamount)", "MaxBurnExceeded"),
+        ];
+        for (message, expected) in cases {
+            assert_eq!(variant(message), Some(expected), "{}", message);
+        }
+        assert_eq!(variant("bad-txns-vout-negative"), None);
+        let transport = bitcoincore_rpc::Error::ReturnedError("boom".into());
+        assert!(classify_rpc_error(&transport).is_none());
+    }
+}
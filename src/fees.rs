@@ -0,0 +1,81 @@
+//! Where target feerates come from: the node's `estimatesmartfee` by default,
+//! or a fixed `--feerate` when the node's estimate can't be trusted (regtest,
+//! a freshly synced node) or a run needs deterministic fees.
+
+use anyhow::{anyhow, Result};
+use bitcoincore_rpc::json::EstimateMode;
+use bitcoincore_rpc::RpcApi;
+
+use crate::bench::MeteredClient;
+use crate::fmt::feerate;
+use crate::node::Node;
+
+/// Blocks within which the demos aim to get an acceleration confirmed.
+pub const CONF_TARGET: u16 = 2;
+
+/// A source of target feerates.
+pub trait FeeEstimator {
+    /// Feerate in sat/vB expected to confirm within `conf_target` blocks.
+    fn estimate(&self, conf_target: u16) -> Result<f64>;
+}
+
+/// Asks the node (`estimatesmartfee`).
+pub struct RpcFeeEstimator<'a> {
+    rpc: &'a MeteredClient,
+    /// `None` leaves the node's default (conservative) mode.
+    mode: Option<EstimateMode>,
+}
+
+impl FeeEstimator for RpcFeeEstimator<'_> {
+    fn estimate(&self, conf_target: u16) -> Result<f64> {
+        let result = self.rpc.estimate_smart_fee(conf_target, self.mode)?;
+        let rate = result.fee_rate.ok_or_else(|| {
+            anyhow!("estimatesmartfee has no estimate: {}", result.errors.unwrap_or_default().join("; "))
+        })?;
+        Ok(rate.to_sat() as f64 / 1000.0) // per kvB → sat/vB
+    }
+}
+
+/// The same feerate for every target (`--feerate`).
+pub struct FixedFeerate(pub f64);
+
+impl FeeEstimator for FixedFeerate {
+    fn estimate(&self, _conf_target: u16) -> Result<f64> {
+        Ok(self.0)
+    }
+}
+
+/// `estimator`'s feerate for [`CONF_TARGET`], or `None` (saying why) if it has none.
+pub fn target_feerate(estimator: &dyn FeeEstimator) -> Option<f64> {
+    match estimator.estimate(CONF_TARGET) {
+        Ok(rate) => {
+            say!("📈 Target feerate for {} blocks: {}", CONF_TARGET, feerate(rate));
+            Some(rate)
+        }
+        Err(e) => {
+            say!("📈 No target feerate ({}), pass --feerate to set one", e);
+            None
+        }
+    }
+}
+
+impl Node {
+    /// `--feerate` if given, otherwise the node's own estimate.
+    pub fn fee_estimator(&self) -> Box<dyn FeeEstimator + '_> {
+        match self.opts.feerate {
+            Some(rate) => Box::new(FixedFeerate(rate)),
+            None => Box::new(RpcFeeEstimator { rpc: &self.rpc, mode: None }),
+        }
+    }
+
+    /// Feerate to confirm within `blocks`: `--feerate` if given, otherwise the
+    /// node's `ECONOMICAL` estimate, the mode wallets use for "priority" choices.
+    pub fn deadline_feerate(&self, blocks: u16) -> Result<f64> {
+        let rate = match self.opts.feerate {
+            Some(rate) => FixedFeerate(rate).estimate(blocks)?,
+            None => RpcFeeEstimator { rpc: &self.rpc, mode: Some(EstimateMode::Economical) }.estimate(blocks)?,
+        };
+        say!("📈 Feerate to confirm within {} blocks: {}", blocks, feerate(rate));
+        Ok(rate)
+    }
+}
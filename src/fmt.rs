@@ -0,0 +1,61 @@
+//! One way to print money: every amount and feerate the demos show goes
+//! through these helpers, and `--unit` switches every amount to one unit.
+
+use anyhow::bail;
+use bitcoin::Amount;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+/// The unit every amount is written in with `--unit`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    Btc,
+    Mbtc,
+    Sat,
+}
+
+impl FromStr for Unit {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "btc" => Ok(Unit::Btc),
+            "mbtc" => Ok(Unit::Mbtc),
+            "sat" | "sats" => Ok(Unit::Sat),
+            other => bail!("unknown unit '{}': expected btc, mbtc or sat", other),
+        }
+    }
+}
+
+static UNIT: OnceLock<Unit> = OnceLock::new();
+
+/// Writes every amount in `unit` for the rest of the run; later calls are
+/// ignored. Without it, [`btc`] and [`sats`] each keep their own unit.
+pub fn init(unit: Unit) {
+    let _ = UNIT.set(unit);
+}
+
+/// `a` in `unit`, with that unit's full precision so amounts line up.
+pub fn amount_in(a: Amount, unit: Unit) -> String {
+    let sat = a.to_sat();
+    match unit {
+        Unit::Btc => format!("{}.{:08} BTC", sat / 100_000_000, sat % 100_000_000),
+        Unit::Mbtc => format!("{}.{:05} mBTC", sat / 100_000, sat % 100_000),
+        Unit::Sat => format!("{} sats", sat),
+    }
+}
+
+/// `0.00010000 BTC`: always eight decimals, so amounts line up and compare.
+pub fn btc(a: Amount) -> String {
+    amount_in(a, UNIT.get().copied().unwrap_or(Unit::Btc))
+}
+
+/// `10000 sats`.
+pub fn sats(a: Amount) -> String {
+    amount_in(a, UNIT.get().copied().unwrap_or(Unit::Sat))
+}
+
+/// `12.34 sat/vB`.
+pub fn feerate(r: f64) -> String {
+    format!("{:.2} sat/vB", r)
+}
@@ -0,0 +1,302 @@
+//! `inspect <txid>`: the acceleration-relevant properties of any transaction the
+//! node can see, in the mempool or on chain.
+
+use anyhow::{anyhow, Result};
+use bitcoin::{Address, Amount, OutPoint, Script, Transaction, TxIn, TxOut, Txid};
+use bitcoincore_rpc::RpcApi;
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::fmt::{btc, feerate};
+use crate::node::Node;
+use crate::p2a::is_p2a;
+use crate::sim::{self, SizeBreakdown};
+
+/// Short name for the standard template `script` matches.
+pub fn script_kind(script: &Script) -> &'static str {
+    if is_p2a(script) {
+        "P2A"
+    } else if script.is_p2pkh() {
+        "P2PKH"
+    } else if script.is_p2sh() {
+        "P2SH"
+    } else if script.is_p2wpkh() {
+        "P2WPKH"
+    } else if script.is_p2wsh() {
+        "P2WSH"
+    } else if script.is_p2tr() {
+        "P2TR"
+    } else if script.is_op_return() {
+        "OP_RETURN"
+    } else {
+        "non-standard"
+    }
+}
+
+/// What an input spends, which decides how many bytes it adds and whether
+/// they get the witness discount.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum InputType {
+    P2pkh,
+    P2sh,
+    /// P2SH wrapping a segwit program: redeemScript in the scriptSig, signature in the witness.
+    P2shSegwit,
+    P2wpkh,
+    P2wsh,
+    P2tr,
+    /// A keyless P2A anchor: empty scriptSig and witness.
+    P2a,
+    Coinbase,
+    NonStandard,
+    /// The previous transaction couldn't be looked up.
+    Unknown,
+}
+
+impl InputType {
+    /// Whether the input's spending data sits in the witness, at a quarter of the cost.
+    pub fn is_segwit(self) -> bool {
+        matches!(self, InputType::P2shSegwit | InputType::P2wpkh | InputType::P2wsh | InputType::P2tr | InputType::P2a)
+    }
+
+    pub fn is_legacy(self) -> bool {
+        matches!(self, InputType::P2pkh | InputType::P2sh)
+    }
+}
+
+impl fmt::Display for InputType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            InputType::P2pkh => "P2PKH",
+            InputType::P2sh => "P2SH",
+            InputType::P2shSegwit => "P2SH-segwit",
+            InputType::P2wpkh => "P2WPKH",
+            InputType::P2wsh => "P2WSH",
+            InputType::P2tr => "P2TR",
+            InputType::P2a => "P2A anchor",
+            InputType::Coinbase => "coinbase",
+            InputType::NonStandard => "non-standard",
+            InputType::Unknown => "unknown type",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Classifies `input` by the scriptPubKey of the output it spends, `Unknown`
+/// when that output isn't available.
+fn input_type(input: &TxIn, spent: Option<&TxOut>) -> InputType {
+    let Some(spent) = spent else {
+        return InputType::Unknown;
+    };
+    let script = &spent.script_pubkey;
+    if is_p2a(script) {
+        InputType::P2a
+    } else if script.is_p2pkh() {
+        InputType::P2pkh
+    } else if script.is_p2sh() && !input.witness.is_empty() {
+        InputType::P2shSegwit
+    } else if script.is_p2sh() {
+        InputType::P2sh
+    } else if script.is_p2wpkh() {
+        InputType::P2wpkh
+    } else if script.is_p2wsh() {
+        InputType::P2wsh
+    } else if script.is_p2tr() {
+        InputType::P2tr
+    } else {
+        InputType::NonStandard
+    }
+}
+
+/// The first P2A anchor among the outputs `tx` spends, given as
+/// [`Node::spent_outputs`] returns them.
+fn anchor_spent(tx: &Transaction, spent: &[Option<TxOut>]) -> Option<OutPoint> {
+    tx.input
+        .iter()
+        .zip(spent)
+        .find(|(_, spent)| spent.as_ref().is_some_and(|o| is_p2a(&o.script_pubkey)))
+        .map(|(input, _)| input.previous_output)
+}
+
+pub struct InputReport {
+    pub prevout: OutPoint,
+    /// `None` for coinbase inputs or when the previous transaction isn't available.
+    pub value: Option<Amount>,
+    pub kind: InputType,
+}
+
+pub struct OutputReport {
+    pub value: Amount,
+    pub address: Option<Address>,
+    pub kind: &'static str,
+}
+
+/// Everything [`Node::inspect`] found out about one transaction.
+pub struct TxReport {
+    pub txid: Txid,
+    pub version: i32,
+    pub sizes: SizeBreakdown,
+    /// `None` when some input's value couldn't be looked up.
+    pub fee: Option<Amount>,
+    pub signals_rbf: bool,
+    /// The P2A anchor this transaction spends, if any.
+    pub spends_anchor: Option<OutPoint>,
+    pub inputs: Vec<InputReport>,
+    pub outputs: Vec<OutputReport>,
+}
+
+impl TxReport {
+    pub fn has_p2a_anchor(&self) -> bool {
+        self.outputs.iter().any(|o| o.kind == "P2A")
+    }
+}
+
+impl fmt::Display for TxReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "🔎 Transaction {}", self.txid)?;
+        writeln!(f, "   ├─ Version: {}", self.version)?;
+        writeln!(f, "   ├─ Size: {}", self.sizes)?;
+        match self.fee {
+            Some(fee) => writeln!(f, "   ├─ Fee: {} ({})", btc(fee), feerate(sim::feerate(fee, self.sizes.vsize)))?,
+            None => writeln!(f, "   ├─ Fee: unknown (previous outputs not available)")?,
+        }
+        writeln!(f, "   ├─ RBF signalling: {}", if self.signals_rbf { "✅ YES" } else { "❌ NO" })?;
+        writeln!(f, "   ├─ P2A anchor: {}", if self.has_p2a_anchor() { "✅ YES" } else { "❌ NO" })?;
+        match self.spends_anchor {
+            Some(anchor) => writeln!(f, "   ├─ Spends P2A anchor: ✅ {}", anchor)?,
+            None => writeln!(f, "   ├─ Spends P2A anchor: ❌ NO")?,
+        }
+        let segwit = self.inputs.iter().filter(|i| i.kind.is_segwit()).count();
+        let legacy = self.inputs.iter().filter(|i| i.kind.is_legacy()).count();
+        writeln!(f, "   ├─ Inputs: {} ({} segwit, {} legacy)", self.inputs.len(), segwit, legacy)?;
+        for (i, input) in self.inputs.iter().enumerate() {
+            let branch = if i + 1 == self.inputs.len() && legacy == 0 { "└─" } else { "├─" };
+            match input.value {
+                Some(value) => writeln!(f, "   │   {} {} ({}, {})", branch, input.prevout, btc(value), input.kind)?,
+                None => writeln!(f, "   │   {} {} (value unknown, {})", branch, input.prevout, input.kind)?,
+            }
+        }
+        if legacy > 0 {
+            writeln!(f, "   │   └─ 💡 Legacy inputs pay full weight for their signatures: more vbytes to accelerate")?;
+        }
+        write!(f, "   └─ Outputs: {}", self.outputs.len())?;
+        for (vout, output) in self.outputs.iter().enumerate() {
+            let address = output.address.as_ref().map_or("-".to_string(), Address::to_string);
+            let branch = if vout + 1 == self.outputs.len() { "└─" } else { "├─" };
+            write!(f, "\n       {} #{}: {}, {} {}", branch, vout, btc(output.value), output.kind, address)?;
+        }
+        Ok(())
+    }
+}
+
+impl Node {
+    /// Looks a transaction up in the mempool or on chain (needs `-txindex` for
+    /// confirmed transactions), falling back to the wallet's own records.
+    fn fetch_tx(&self, txid: Txid) -> Result<Transaction> {
+        match self.rpc.get_raw_transaction(&txid, None) {
+            Ok(tx) => Ok(tx),
+            Err(_) => Ok(self.rpc.get_transaction(&txid, None)?.transaction()?),
+        }
+    }
+
+    /// Total the inputs of `tx` spend, or `None` when some previous output
+    /// can't be looked up (pruned node, no `-txindex`).
+    fn input_total(&self, tx: &Transaction) -> Option<Amount> {
+        self.spent_outputs(tx).into_iter().map(|spent| spent.map(|o| o.value)).sum()
+    }
+
+    /// `--prevout-amount` as the total `txid`'s inputs spend, with a warning
+    /// that nothing checked it.
+    fn asserted_input_total(&self, txid: Txid) -> Result<Amount> {
+        let total = self.opts.prevout_amount.ok_or_else(|| {
+            anyhow!("can't look up what {} spends (pruned node or no -txindex): pass --prevout-amount <sats>", txid)
+        })?;
+        say!("⚠️  Fee of {} computed from --prevout-amount {}: user-asserted, not checked against the chain", txid, btc(total));
+        Ok(total)
+    }
+
+    /// Fee `tx` pays: the node's own figure while it is in the mempool,
+    /// otherwise its inputs less its outputs, falling back to `--prevout-amount`
+    /// for the inputs when their previous outputs can't be looked up.
+    pub fn fee_of(&self, tx: &Transaction) -> Result<Amount> {
+        let txid = tx.compute_txid();
+        if let Ok(fee) = self.mempool_fee(txid) {
+            return Ok(fee);
+        }
+        let input_total = match self.input_total(tx) {
+            Some(total) => total,
+            None => self.asserted_input_total(txid)?,
+        };
+        let output_total: Amount = tx.output.iter().map(|o| o.value).sum();
+        input_total
+            .checked_sub(output_total)
+            .ok_or_else(|| anyhow!("{} spends {} but pays out {}", txid, btc(input_total), btc(output_total)))
+    }
+
+    /// The output each input of `tx` spends, looking up every previous
+    /// transaction once; `None` where it isn't available, and for a coinbase.
+    fn spent_outputs(&self, tx: &Transaction) -> Vec<Option<TxOut>> {
+        if tx.is_coinbase() {
+            return vec![None; tx.input.len()];
+        }
+        let mut prev_txs: HashMap<Txid, Option<Transaction>> = HashMap::new();
+        tx.input
+            .iter()
+            .map(|input| {
+                let prevout = input.previous_output;
+                let prev = prev_txs.entry(prevout.txid).or_insert_with(|| self.fetch_tx(prevout.txid).ok());
+                prev.as_ref()?.output.get(prevout.vout as usize).cloned()
+            })
+            .collect()
+    }
+
+    /// The P2A anchor `txid` spends, found by looking up each input's previous
+    /// transaction (the anchor is gone from the UTXO set once spent).
+    pub fn is_anchor_spend(&self, txid: Txid) -> Result<Option<OutPoint>> {
+        let tx = self.fetch_tx(txid)?;
+        Ok(anchor_spent(&tx, &self.spent_outputs(&tx)))
+    }
+
+    pub fn inspect(&self, txid: Txid) -> Result<TxReport> {
+        let tx = self.fetch_tx(txid)?;
+        let network = self.context.chain;
+        let spent = self.spent_outputs(&tx);
+
+        let inputs: Vec<InputReport> = tx
+            .input
+            .iter()
+            .zip(&spent)
+            .map(|(input, spent)| InputReport {
+                prevout: input.previous_output,
+                value: spent.as_ref().map(|o| o.value),
+                kind: if tx.is_coinbase() { InputType::Coinbase } else { input_type(input, spent.as_ref()) },
+            })
+            .collect();
+
+        let outputs: Vec<OutputReport> = tx
+            .output
+            .iter()
+            .map(|output| OutputReport {
+                value: output.value,
+                address: Address::from_script(&output.script_pubkey, network).ok(),
+                kind: script_kind(&output.script_pubkey),
+            })
+            .collect();
+
+        let input_total: Option<Amount> = match inputs.iter().map(|i| i.value).sum() {
+            None if !tx.is_coinbase() => self.asserted_input_total(txid).ok(),
+            known => known,
+        };
+        let output_total: Amount = outputs.iter().map(|o| o.value).sum();
+
+        Ok(TxReport {
+            txid,
+            version: tx.version.0,
+            sizes: sim::size_breakdown(&tx),
+            fee: input_total.and_then(|total| total.checked_sub(output_total)),
+            signals_rbf: tx.is_explicitly_rbf(),
+            spends_anchor: anchor_spent(&tx, &spent),
+            inputs,
+            outputs,
+        })
+    }
+}
@@ -1,38 +1,150 @@
+#[macro_use]
+mod output;
 mod rbf;
 mod cpfp;
 mod p2a;
+mod bench;
+mod builder;
+mod capabilities;
+mod cli_script;
+mod consts;
+mod context;
+mod deadline;
+mod dot;
+mod dump;
+mod errors;
+mod fees;
+mod fmt;
+mod inspect;
+mod mempool;
+mod monitor;
+mod narrative;
+mod node;
+mod options;
+mod psbt;
+mod receipt;
+mod sim;
+#[cfg(feature = "regtest-tests")]
+mod selftest;
 
 use anyhow::Result;
 use std::io;
 
+use node::Node;
+use options::Options;
+use receipt::Outcome;
+
 #[tokio::main]
 async fn main() -> Result<()> {
-    println!("🚀 Bitcoin Transaction Acceleration Demo\n");
+    #[cfg(feature = "regtest-tests")]
+    if std::env::args().any(|arg| arg == "--self-test") {
+        return selftest::run().await;
+    }
+
+    let opts = Options::from_args()?;
+    output::init(output::Style { plain: opts.plain, color: opts.color });
+    if let Some(unit) = opts.unit {
+        fmt::init(unit);
+    }
+    if let Some(txid) = opts.inspect {
+        say!("{}", Node::connect(&opts)?.inspect(txid)?);
+        return Ok(());
+    }
+    if let Some(target) = opts.accelerate {
+        return deadline::run(&opts, target);
+    }
+    if let Some(target) = opts.autopilot {
+        return monitor::run_autopilot(&opts, target).await;
+    }
+    if let Some(path) = &opts.bump_psbt {
+        return psbt::run_bump(&opts, path);
+    }
+
+    say!("🚀 Bitcoin Transaction Acceleration Demo\n");
     
-    println!("Select a demonstration:");
-    println!("1. RBF (Replace-by-Fee)");
-    println!("2. CPFP (Child-Pays-for-Parent)");
-    println!("3. P2A (Ephemeral Anchors)");
-    println!("\nEnter your choice (1-3): ");
+    say!("Select a demonstration:");
+    say!("1. RBF (Replace-by-Fee)");
+    say!("2. CPFP (Child-Pays-for-Parent)");
+    say!("3. P2A (Ephemeral Anchors)");
+    say!("4. Batch CPFP (concurrent acceleration)");
+    say!("5. RBF with wallet coin selection (fundrawtransaction)");
+    say!("6. Fee planner (offline, no node needed)");
+    say!("7. RBF: cancel one payment in a batch");
+    say!("8. RBF: cancel a payment (send it back to yourself)");
+    say!("9. RBF: replace a transaction with unconfirmed parents");
+    say!("10. RBF: replace a CPFP parent, keeping its child");
+    say!("11. RBF: win back a UTXO from a double-spend and its descendants");
+    say!("12. RBF: add a forgotten recipient to a payment");
+    say!("13. RBF: try to replace a transaction that doesn't signal RBF");
+    say!("\nEnter your choice (1-13): ");
 
     let mut input = String::new();
     io::stdin().read_line(&mut input)?;
     
     match input.trim() {
         "1" => {
-            println!("🔄 Starting RBF Demo...\n");
-            rbf::run_demo().await?;
+            say!("🔄 Starting RBF Demo...\n");
+            if let Some(outcome) = rbf::run_demo(&opts).await? {
+                say!("\n🧾 {}", outcome);
+                receipt::emit(&opts, &Outcome::Rbf(outcome))?;
+            }
         },
         "2" => {
-            println!("🔄 Starting CPFP Demo...\n");
-            cpfp::run_demo().await?;
+            say!("🔄 Starting CPFP Demo...\n");
+            if let Some(outcome) = cpfp::run_demo(&opts).await? {
+                say!("\n🧾 {}", outcome);
+                receipt::emit(&opts, &Outcome::Cpfp(outcome))?;
+            }
         },
         "3" => {
-            println!("🔄 Starting P2A Demo...\n");
-            p2a::run_demo().await?;
+            say!("🔄 Starting P2A Demo...\n");
+            if let Some(outcome) = p2a::run_demo(&opts).await? {
+                say!("\n🧾 {}", outcome);
+                receipt::emit(&opts, &Outcome::P2a(outcome))?;
+            }
+        },
+        "4" => {
+            say!("🔄 Starting Batch CPFP Demo...\n");
+            cpfp::run_batch_demo(&opts).await?;
+        },
+        "5" => {
+            say!("🔄 Starting Wallet-Funded RBF Demo...\n");
+            rbf::run_funded_demo(&opts).await?;
+        },
+        "6" => {
+            say!("🔄 Starting Fee Planner...\n");
+            sim::run_planner()?;
+        },
+        "7" => {
+            say!("🔄 Starting Batch-Cancel RBF Demo...\n");
+            rbf::run_drop_output_demo(&opts).await?;
+        },
+        "8" => {
+            say!("🔄 Starting Cancel RBF Demo...\n");
+            rbf::run_cancel_demo(&opts).await?;
+        },
+        "9" => {
+            say!("🔄 Starting Cluster RBF Demo...\n");
+            rbf::run_cluster_demo(&opts).await?;
+        },
+        "10" => {
+            say!("🔄 Starting Parent-and-Child RBF Demo...\n");
+            rbf::run_parent_child_demo(&opts).await?;
+        },
+        "11" => {
+            say!("🔄 Starting Conflict-Set RBF Demo...\n");
+            rbf::run_conflict_demo(&opts).await?;
+        },
+        "12" => {
+            say!("🔄 Starting Add-Recipient RBF Demo...\n");
+            rbf::run_add_recipient_demo(&opts).await?;
+        },
+        "13" => {
+            say!("🔄 Starting Non-Signalling RBF Demo...\n");
+            rbf::run_rejection_demo(&opts).await?;
         },
         _ => {
-            println!("❌ Invalid choice. Please run again and select 1, 2, or 3.");
+            say!("❌ Invalid choice. Please run again and select 1-13.");
             return Ok(());
         }
     }
@@ -0,0 +1,219 @@
+//! What a broadcast or replacement actually did to the node's mempool, as the
+//! difference between two `getrawmempool` snapshots, what the mempool looks
+//! like by feerate, where an accelerated package ranks in it, and `--flood` to
+//! give it a low-fee backlog.
+
+use anyhow::{ensure, Result};
+use bitcoin::{Amount, OutPoint, Txid};
+use bitcoincore_rpc::RpcApi;
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::consts::SEQUENCE_NO_RBF;
+use crate::fmt::{btc, feerate};
+use crate::node::Node;
+use crate::sim;
+
+/// Lower bounds (sat/vB) of the fee histogram buckets.
+const FEE_BUCKETS: [f64; 14] = [0.0, 1.0, 2.0, 3.0, 5.0, 8.0, 10.0, 15.0, 20.0, 30.0, 50.0, 100.0, 200.0, 500.0];
+
+/// Width of the longest histogram bar.
+const HISTOGRAM_WIDTH: u64 = 40;
+
+/// What each `--flood` transaction pays; the rest of its input goes back as change.
+const FLOOD_PAYMENT: Amount = Amount::from_sat(10_000);
+
+/// Confirmed UTXOs `--flood` leaves alone for the demo itself.
+const FLOOD_RESERVE: usize = 3;
+
+/// Transactions that entered and left the mempool between two snapshots.
+pub struct MempoolDelta {
+    pub added: Vec<Txid>,
+    /// Replaced (or mined) transactions, including any evicted descendants.
+    pub removed: Vec<Txid>,
+}
+
+/// `1st`, `2nd`, `3rd`, `11th`, `22nd`...
+fn ordinal(n: usize) -> String {
+    let suffix = match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{}{}", n, suffix)
+}
+
+/// The set difference between the `before` and `after` mempool snapshots.
+pub fn mempool_delta(before: &[Txid], after: &[Txid]) -> MempoolDelta {
+    let (before_set, after_set): (HashSet<_>, HashSet<_>) = (before.iter().collect(), after.iter().collect());
+    MempoolDelta {
+        added: after.iter().filter(|txid| !before_set.contains(txid)).copied().collect(),
+        removed: before.iter().filter(|txid| !after_set.contains(txid)).copied().collect(),
+    }
+}
+
+impl MempoolDelta {
+    /// Keeps only the txids in `ours`: between two snapshots the rest of the
+    /// network's transactions come and go (and get mined) as well.
+    pub fn only(mut self, ours: &[Txid]) -> Self {
+        let ours: HashSet<_> = ours.iter().collect();
+        self.added.retain(|txid| ours.contains(txid));
+        self.removed.retain(|txid| ours.contains(txid));
+        self
+    }
+}
+
+impl fmt::Display for MempoolDelta {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lines: Vec<String> = self
+            .added
+            .iter()
+            .map(|txid| format!("➕ added   {}", txid))
+            .chain(self.removed.iter().map(|txid| format!("➖ removed {}", txid)))
+            .collect();
+        if lines.is_empty() {
+            return write!(f, "   └─ (no change)");
+        }
+        for (i, line) in lines.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "   {} {}", if i + 1 == lines.len() { "└─" } else { "├─" }, line)?;
+        }
+        Ok(())
+    }
+}
+
+impl Node {
+    /// vbytes waiting in the mempool per feerate bucket, as `(lower bound in
+    /// sat/vB, vbytes)` for the non-empty buckets, lowest first.
+    pub fn fee_histogram(&self) -> Result<Vec<(f64, u64)>> {
+        let mut vbytes = [0u64; FEE_BUCKETS.len()];
+        for entry in self.rpc.get_raw_mempool_verbose()?.values() {
+            let rate = sim::feerate(entry.fees.base, entry.vsize as usize);
+            let bucket = FEE_BUCKETS.iter().rposition(|low| rate >= *low).unwrap_or(0);
+            vbytes[bucket] += entry.vsize;
+        }
+        Ok(FEE_BUCKETS.iter().zip(vbytes).filter(|(_, v)| *v > 0).map(|(low, v)| (*low, v)).collect())
+    }
+
+    /// Prints [`Self::fee_histogram`] as ASCII bars, highest feerate first.
+    pub fn print_fee_histogram(&self) -> Result<()> {
+        let histogram = self.fee_histogram()?;
+        if histogram.is_empty() {
+            say!("📊 Mempool fee histogram: mempool empty\n");
+            return Ok(());
+        }
+        let max = histogram.iter().map(|(_, v)| *v).max().unwrap_or(1);
+        say!("📊 Mempool fee histogram (vbytes waiting per feerate):");
+        for (i, (low, v)) in histogram.iter().rev().enumerate() {
+            let bar = "█".repeat((v * HISTOGRAM_WIDTH).div_ceil(max) as usize);
+            let branch = if i + 1 == histogram.len() { "└─" } else { "├─" };
+            say!("   {} ≥ {:>12} {} {} vB", branch, feerate(*low), bar, v);
+        }
+        say!();
+        Ok(())
+    }
+
+    /// Where a package at `feerate` sat/vB stands in line for the next blocks:
+    /// `(position, total)`, where position is one plus the number of mempool
+    /// entries whose ancestor feerate (what miners select by) beats it, and
+    /// total is the mempool's size, at least the position.
+    pub fn rank_among_mempool(&self, feerate: f64) -> Result<(usize, usize)> {
+        let entries = self.rpc.get_raw_mempool_verbose()?;
+        let ahead = entries
+            .values()
+            .filter(|entry| sim::feerate(entry.fees.ancestor, entry.ancestor_size as usize) > feerate)
+            .count();
+        Ok((ahead + 1, entries.len().max(ahead + 1)))
+    }
+
+    /// Prints [`Self::rank_among_mempool`]: "ranks 3rd of 120 by feerate".
+    pub fn print_rank(&self, what: &str, rate: f64) -> Result<()> {
+        let (position, total) = self.rank_among_mempool(rate)?;
+        say!("🏁 Your {} ({}) ranks {} of {} in the mempool by feerate", what, feerate(rate), ordinal(position), total);
+        Ok(())
+    }
+
+    /// Broadcasts `count` small payments at `feerate` sat/vB, each spending its
+    /// own confirmed wallet UTXO: a low-fee backlog for the acceleration to
+    /// jump. Confirmed inputs only, so no unconfirmed chain can hit the
+    /// ancestor limits, and [`FLOOD_RESERVE`] UTXOs stay free for the demo.
+    pub fn flood_low_fee(&self, count: usize, feerate: f64) -> Result<Vec<Txid>> {
+        ensure!(
+            feerate >= self.context.min_relay_feerate(),
+            "flood feerate {} is below the node's minimum relay feerate {}",
+            crate::fmt::feerate(feerate), crate::fmt::feerate(self.context.min_relay_feerate())
+        );
+        let mut utxos = Vec::new();
+        for utxo in self.rpc.list_unspent(Some(1), None, None, None, None)? {
+            if utxo.amount > FLOOD_PAYMENT * 2 && self.is_mature(&utxo)? {
+                utxos.push(utxo);
+            }
+        }
+        ensure!(
+            utxos.len() >= count + FLOOD_RESERVE,
+            "--flood {} needs {} mature confirmed UTXOs ({} kept for the demo), the wallet has {}: mine more blocks",
+            count, count + FLOOD_RESERVE, FLOOD_RESERVE, utxos.len()
+        );
+
+        let dest = self.rpc.get_new_address(None, None)?.assume_checked();
+        let mut txids = Vec::with_capacity(count);
+        for utxo in utxos.iter().take(count) {
+            let outpoint = OutPoint::new(utxo.txid, utxo.vout);
+            let build = |fee: Amount| -> Result<_> {
+                Ok(self.tx_builder()?
+                    .input(outpoint, SEQUENCE_NO_RBF)
+                    .pay(FLOOD_PAYMENT, &dest)
+                    .pay(utxo.amount - FLOOD_PAYMENT - fee, self.change_address()?)
+                    .build())
+            };
+            // Sign once at zero fee to learn the weight, then pay for it
+            let weight = self.sign_with_wallet(&build(Amount::ZERO)?)?.transaction()?.weight().to_wu() as usize;
+            let fee = sim::fee_from_feerate(weight, feerate);
+            txids.push(self.broadcast(&self.sign_with_wallet(&build(fee)?)?.hex, None)?);
+        }
+        Ok(txids)
+    }
+
+    /// With `--flood <count>`, fills the mempool with that many payments at
+    /// the minimum relay feerate and shows the resulting histogram.
+    pub fn flood_if_requested(&self) -> Result<()> {
+        if self.opts.flood == 0 {
+            return Ok(());
+        }
+        let rate = self.context.min_relay_feerate();
+        let txids = self.flood_low_fee(self.opts.flood, rate)?;
+        say!("🌊 Flooded the mempool with {} low-fee payments ({} each at {})", txids.len(), btc(FLOOD_PAYMENT), feerate(rate));
+        self.print_fee_histogram()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::hashes::Hash;
+
+    fn txid(n: u8) -> Txid {
+        Txid::from_byte_array([n; 32])
+    }
+
+    #[test]
+    fn delta_reports_added_and_removed_but_not_unchanged() {
+        let delta = mempool_delta(&[txid(1), txid(2)], &[txid(2), txid(3)]);
+        assert_eq!(delta.added, [txid(3)]);
+        assert_eq!(delta.removed, [txid(1)]);
+
+        let unchanged = mempool_delta(&[txid(1), txid(2)], &[txid(2), txid(1)]);
+        assert!(unchanged.added.is_empty() && unchanged.removed.is_empty());
+    }
+
+    #[test]
+    fn only_drops_other_transactions() {
+        let delta = mempool_delta(&[txid(1), txid(2)], &[txid(3), txid(4)]).only(&[txid(1), txid(3)]);
+        assert_eq!(delta.added, [txid(3)]);
+        assert_eq!(delta.removed, [txid(1)]);
+    }
+}
@@ -0,0 +1,335 @@
+//! Getting transactions into a block, behind the [`Confirmer`] trait: on
+//! regtest the demos mine their own blocks ([`RegtestMiner`]); on signet or
+//! testnet they can't, so they poll until a real miner confirms the
+//! accelerated transaction instead ([`WaitForMiner`]). Either way, [`Node::print_block_stats`] then
+//! shows how the accelerated package compares with the rest of its block.
+//! `autopilot <txid:vout>` waits and bumps in a loop until a spend confirms.
+
+use anyhow::{anyhow, ensure, Result};
+use bitcoin::{Address, Amount, BlockHash, Network, OutPoint, Txid};
+use bitcoincore_rpc::RpcApi;
+use serde_json::{json, Value};
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::bench::MeteredClient;
+use crate::capabilities::Feature;
+use crate::consts::SEQUENCE_RBF;
+use crate::fees;
+use crate::fmt::{btc, feerate};
+use crate::node::Node;
+use crate::options::Options;
+use crate::sim;
+
+/// Factor the autopilot raises its feerate by after each block that passes
+/// without a confirmation.
+const AUTOPILOT_STEP: f64 = 1.5;
+
+/// Blocks the autopilot's first, cheapest attempt aims for.
+const AUTOPILOT_START_TARGET: u16 = 144;
+
+/// Feerate (sat/vB) the simulated regtest miner demands without `--feerate`.
+const AUTOPILOT_MARKET_FEERATE: f64 = 10.0;
+
+/// How every wait polls the node: `--poll-ms` and `--timeout-s`.
+#[derive(Debug, Clone, Copy)]
+pub struct Timing {
+    /// How often to ask the node again.
+    pub poll_interval: Duration,
+    /// How long to wait before giving up.
+    pub timeout: Duration,
+}
+
+impl Default for Timing {
+    fn default() -> Self {
+        Timing { poll_interval: Duration::from_secs(30), timeout: Duration::from_secs(3 * 60 * 60) }
+    }
+}
+
+/// A wait that ran out of time.
+#[derive(Debug)]
+pub enum WaitError {
+    Timeout { txid: Txid, timeout: Duration },
+    /// The autopilot needed to bump past its `--max-feerate`.
+    FeerateCap { txid: Txid, max_feerate: f64 },
+}
+
+impl fmt::Display for WaitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WaitError::Timeout { txid, timeout } => {
+                write!(f, "{} still unconfirmed after {}s (raise --timeout-s to wait longer)", txid, timeout.as_secs())
+            }
+            WaitError::FeerateCap { txid, max_feerate } => write!(
+                f,
+                "{} already pays the {} cap and still didn't confirm (raise --max-feerate to keep bumping)",
+                txid, feerate(*max_feerate)
+            ),
+        }
+    }
+}
+
+impl std::error::Error for WaitError {}
+
+/// How the demos get their transactions into a block. Implementations may
+/// block for as long as a real miner takes; [`Node::confirm`] runs them on
+/// tokio's blocking pool.
+pub trait Confirmer: Send {
+    /// Gets every transaction in `txids` confirmed, or at least a block mined,
+    /// and returns the block to inspect; `None` if there was none to wait for.
+    fn confirm(&self, rpc: &MeteredClient, txids: &[Txid]) -> Result<Option<BlockHash>>;
+}
+
+/// Regtest: mines the next block itself (`generatetoaddress`), whatever ends
+/// up in it.
+pub struct RegtestMiner {
+    pub miner_addr: Address,
+}
+
+impl Confirmer for RegtestMiner {
+    fn confirm(&self, rpc: &MeteredClient, _txids: &[Txid]) -> Result<Option<BlockHash>> {
+        say!("⛏️  Mining block...");
+        Ok(rpc.generate_to_address(1, &self.miner_addr)?.first().copied())
+    }
+}
+
+/// Any other network: polls until a real miner confirms every transaction.
+pub struct WaitForMiner {
+    pub timing: Timing,
+}
+
+impl Confirmer for WaitForMiner {
+    /// Returns the block that confirmed the last of `txids`.
+    fn confirm(&self, rpc: &MeteredClient, txids: &[Txid]) -> Result<Option<BlockHash>> {
+        let mut block = None;
+        for txid in txids {
+            let info = wait_until_confirmed(rpc, *txid, self.timing)?;
+            say!("⏱️  Confirmed at height {} (block time {})", info.block_height, info.block_time);
+            say!("   └─ Waited {} block(s), {} min", info.blocks_waited, info.waited.as_secs() / 60);
+            block = Some(info.block_hash);
+        }
+        Ok(block)
+    }
+}
+
+/// Polls the wallet every `timing.poll_interval` until `txid` confirms,
+/// failing if it leaves the mempool unconfirmed (replaced or evicted) or
+/// with [`WaitError::Timeout`] once `timing.timeout` passes.
+pub fn wait_until_confirmed(rpc: &MeteredClient, txid: Txid, timing: Timing) -> Result<ConfirmationInfo> {
+    let start = Instant::now();
+    let start_height = rpc.get_block_count()?;
+    say!("👀 Waiting for {} to confirm (polling every {} ms)...", txid, timing.poll_interval.as_millis());
+    loop {
+        let info = rpc.get_transaction(&txid, None)?.info;
+        if info.confirmations > 0 {
+            let block_hash = info.blockhash.ok_or_else(|| anyhow!("{} confirmed without a block hash", txid))?;
+            let block_height = info.blockheight.ok_or_else(|| anyhow!("{} confirmed without a block height", txid))?;
+            return Ok(ConfirmationInfo {
+                block_hash,
+                block_height,
+                block_time: info.blocktime.unwrap_or_default(),
+                blocks_waited: u64::from(block_height).saturating_sub(start_height),
+                waited: start.elapsed(),
+            });
+        }
+        if rpc.get_mempool_entry(&txid).is_err() {
+            // A block may have confirmed it since the wallet was asked: look again before giving up
+            ensure!(
+                rpc.get_transaction(&txid, None)?.info.confirmations > 0,
+                "{} left the mempool without confirming (replaced or evicted)", txid
+            );
+            continue;
+        }
+        if start.elapsed() > timing.timeout {
+            return Err(WaitError::Timeout { txid, timeout: timing.timeout }.into());
+        }
+        std::thread::sleep(timing.poll_interval);
+    }
+}
+
+/// Where and when a monitored transaction confirmed.
+pub struct ConfirmationInfo {
+    pub block_hash: BlockHash,
+    pub block_height: u32,
+    /// Block timestamp (seconds since the epoch).
+    pub block_time: u64,
+    /// Blocks found between starting to watch and the confirming one, inclusive.
+    pub blocks_waited: u64,
+    pub waited: Duration,
+}
+
+/// Fee and weight totals of a mined block, coinbase excluded (`getblockstats`).
+pub struct BlockStats {
+    /// Transactions besides the coinbase.
+    pub txs: u64,
+    pub total_fee: Amount,
+    pub total_weight: u64,
+    /// sat/vB: the block's total fee over its total vsize, and the extremes.
+    pub avg_feerate: f64,
+    pub min_feerate: f64,
+    pub max_feerate: f64,
+}
+
+impl BlockStats {
+    /// Where a transaction or package paying `rate` sat/vB sits in this block.
+    pub fn placement(&self, rate: f64) -> &'static str {
+        if rate >= self.max_feerate {
+            "the top of the block"
+        } else if rate <= self.min_feerate {
+            "the bottom of the block: it only just made it"
+        } else if rate >= self.avg_feerate {
+            "above the block's average"
+        } else {
+            "below the block's average"
+        }
+    }
+}
+
+impl Node {
+    /// Fee and weight stats of block `hash`. Needs the block's undo data, so a
+    /// pruned node may refuse for older blocks.
+    pub fn block_stats(&self, hash: BlockHash) -> Result<BlockStats> {
+        let fields = ["txs", "totalfee", "total_weight", "minfeerate", "maxfeerate"];
+        let stats: Value = self.rpc.call("getblockstats", &[json!(hash), json!(fields)])?;
+        let field = |name: &str| stats[name].as_u64().ok_or_else(|| anyhow!("getblockstats returned no {}", name));
+        let total_fee = Amount::from_sat(field("totalfee")?);
+        let total_weight = field("total_weight")?;
+        Ok(BlockStats {
+            txs: field("txs")?.saturating_sub(1),
+            total_fee,
+            total_weight,
+            avg_feerate: sim::feerate(total_fee, total_weight.div_ceil(4) as usize),
+            min_feerate: field("minfeerate")? as f64,
+            max_feerate: field("maxfeerate")? as f64,
+        })
+    }
+
+    /// Prints [`Self::block_stats`] for block `hash` and where `package_rate`
+    /// (sat/vB), the accelerated transaction or package spread over
+    /// `package_txs` transactions, sits among the rest. Never fails the demo:
+    /// without stats it just says so.
+    pub fn print_block_stats(&self, hash: BlockHash, package_rate: f64, package_txs: u64) {
+        let stats = match self.block_stats(hash) {
+            Ok(stats) => stats,
+            Err(e) => {
+                say!("📊 No block stats ({})", e);
+                return;
+            }
+        };
+        say!("📊 Block stats ({} transaction(s) besides the coinbase):", stats.txs);
+        say!("   ├─ Total fees: {} over {} WU", btc(stats.total_fee), stats.total_weight);
+        if stats.txs <= package_txs {
+            say!("   └─ Only our {} and the coinbase: nothing else to compare against ({})",
+                 if package_txs == 1 { "transaction" } else { "package" }, feerate(package_rate));
+            return;
+        }
+        say!("   ├─ Feerates: min {}, average {}, max {}", feerate(stats.min_feerate), feerate(stats.avg_feerate), feerate(stats.max_feerate));
+        say!("   └─ Ours at {}: {}", feerate(package_rate), stats.placement(package_rate));
+    }
+
+    /// Spends the wallet's `target` output to `dest` at a cheap feerate, then
+    /// after every block that doesn't confirm it replaces the spend (RBF) at
+    /// [`AUTOPILOT_STEP`] times the feerate, up to `max_feerate`. Stops once a
+    /// spend confirms, with [`WaitError::FeerateCap`] when another bump would
+    /// pass `max_feerate`, or with [`WaitError::Timeout`] after `deadline`. On
+    /// regtest it mines a block per round, taking only transactions above a
+    /// simulated market feerate when `generateblock` is available.
+    pub async fn autopilot(&self, target: OutPoint, dest: &Address, max_feerate: f64, deadline: Duration) -> Result<Txid> {
+        let prevout = self.rpc.get_tx_out(&target.txid, target.vout, Some(true))?
+            .ok_or_else(|| anyhow!("{} is not an unspent output", target))?;
+        ensure!(self.is_mine(&prevout.script_pub_key.script()?)?, "{} does not belong to wallet '{}'", target, self.opts.wallet);
+        let min_relay = self.context.min_relay_feerate();
+        ensure!(max_feerate >= min_relay, "--max-feerate {} is below the minimum relay feerate {}", feerate(max_feerate), feerate(min_relay));
+
+        let build = |fee: Amount| -> Result<_> {
+            let amount = prevout.value.checked_sub(fee)
+                .filter(|amount| *amount >= dest.script_pubkey().minimal_non_dust())
+                .ok_or_else(|| anyhow!("{} ({}) can't cover a {} fee", target, btc(prevout.value), btc(fee)))?;
+            self.sign_with_wallet(&self.tx_builder()?.input(target, SEQUENCE_RBF).pay(amount, dest).build())
+        };
+        // Every attempt has the same shape, so one zero-fee signing gives the size
+        let weight = build(Amount::ZERO)?.transaction()?.weight().to_wu() as usize;
+        let vsize = weight.div_ceil(4);
+        self.confirm_mainnet_fees(&[("Autopilot fee cap", sim::fee_from_feerate(weight, max_feerate))])?;
+
+        let mut rate = self.fee_estimator().estimate(AUTOPILOT_START_TARGET).unwrap_or(min_relay).clamp(min_relay, max_feerate);
+        let mut fee = sim::fee_from_feerate(weight, rate);
+        let mut txid = self.broadcast(&build(fee)?.hex, None)?;
+        say!("🤖 Autopilot: spending {} to {}, cap {}", target, dest, feerate(max_feerate));
+        say!("   ├─ Start: {} at {} (fee {})", txid, feerate(rate), btc(fee));
+
+        let regtest = self.context.chain == Network::Regtest;
+        let market = if regtest {
+            let market = fees::target_feerate(&*self.fee_estimator()).unwrap_or(AUTOPILOT_MARKET_FEERATE);
+            say!("   ├─ Regtest: one block per round, taking only transactions above {}", feerate(market));
+            market
+        } else {
+            0.0
+        };
+        let start = Instant::now();
+        let mut height = self.rpc.get_block_count()?;
+        let mut bumps = 0;
+        loop {
+            if regtest {
+                if self.node_capabilities().has(Feature::GenerateBlock) {
+                    self.mine_above_feerate(market, dest)?;
+                } else {
+                    self.rpc.generate_to_address(1, dest)?;
+                }
+            } else {
+                tokio::time::sleep(self.opts.timing.poll_interval).await;
+            }
+            if self.rpc.get_transaction(&txid, None)?.info.confirmations > 0 {
+                say!("   └─ ✅ {} confirmed after {} bump(s), paying {}", txid, bumps, btc(fee));
+                return Ok(txid);
+            }
+            if start.elapsed() > deadline {
+                return Err(WaitError::Timeout { txid, timeout: deadline }.into());
+            }
+            let tip = self.rpc.get_block_count()?;
+            if tip == height {
+                continue;
+            }
+            height = tip;
+            // BIP125 may need a little more than the step itself
+            let next_fee = sim::rbf_replacement_fee(fee, vsize, (rate * AUTOPILOT_STEP).min(max_feerate), self.context.incremental_fee);
+            if sim::feerate(next_fee, vsize) > max_feerate {
+                return Err(WaitError::FeerateCap { txid, max_feerate }.into());
+            }
+            fee = next_fee;
+            rate = sim::feerate(fee, vsize);
+            txid = self.broadcast(&build(fee)?.hex, None)?;
+            bumps += 1;
+            say!("   ├─ Block {} without it: bumped to {} at {} (fee {})", tip, txid, feerate(rate), btc(fee));
+        }
+    }
+
+    /// The [`Confirmer`] for this node's chain: a [`RegtestMiner`] paying
+    /// `miner_addr` on regtest, a [`WaitForMiner`] with `--poll-ms` and
+    /// `--timeout-s` anywhere else.
+    pub fn confirmer(&self, miner_addr: &Address) -> Box<dyn Confirmer> {
+        if self.context.chain == Network::Regtest {
+            Box::new(RegtestMiner { miner_addr: miner_addr.clone() })
+        } else {
+            Box::new(WaitForMiner { timing: self.opts.timing })
+        }
+    }
+
+    /// Gets `txids` into a block with `confirmer` and returns the block to
+    /// inspect. The wait runs on the blocking pool, not a runtime worker.
+    pub async fn confirm(&self, confirmer: Box<dyn Confirmer>, txids: &[Txid]) -> Result<BlockHash> {
+        let (rpc, txids) = (Arc::clone(&self.rpc), txids.to_vec());
+        let block = tokio::task::spawn_blocking(move || confirmer.confirm(&rpc, &txids)).await??;
+        block.ok_or_else(|| anyhow!("no block to inspect: nothing was confirmed"))
+    }
+}
+
+/// Runs `autopilot <txid:vout>`: spends that output to a fresh wallet address
+/// and keeps bumping until it confirms, within `--max-feerate` and `--timeout-s`.
+pub async fn run_autopilot(opts: &Options, target: OutPoint) -> Result<()> {
+    let node = Node::connect(opts)?;
+    let dest = node.rpc.get_new_address(None, None)?.assume_checked();
+    node.autopilot(target, &dest, opts.max_feerate, opts.timing.timeout).await?;
+    Ok(())
+}
@@ -0,0 +1,142 @@
+//! The shape every demo's story shares: numbered steps, presentation pauses
+//! with their talking points, and the last pause before mining. Each demo
+//! declares its whole story up front as [`DemoStep`] data in a [`Story`], and
+//! [`run_steps`] prints it the same way everywhere.
+
+use std::collections::VecDeque;
+
+use anyhow::Result;
+
+/// One beat of a demo's narrative.
+pub enum DemoStep {
+    /// `📝 STEP n: title`
+    Step { number: u8, title: String },
+    /// `⏸️ [PRESENTATION MOMENT]`: talking points, then what pressing Enter
+    /// (or typing something first) will do.
+    Pause { points: Vec<String>, actions: Vec<String> },
+    /// `⏸️ [FINAL DEMONSTRATION]`: the closing pause before mining.
+    Final { intro: String, actions: Vec<String> },
+}
+
+impl DemoStep {
+    pub fn step(number: u8, title: impl Into<String>) -> Self {
+        DemoStep::Step { number, title: title.into() }
+    }
+
+    pub fn pause(points: &[&str], actions: &[&str]) -> Self {
+        DemoStep::Pause { points: owned(points), actions: owned(actions) }
+    }
+
+    pub fn final_demo(intro: impl Into<String>, actions: &[&str]) -> Self {
+        DemoStep::Final { intro: intro.into(), actions: owned(actions) }
+    }
+}
+
+fn owned(lines: &[&str]) -> Vec<String> {
+    lines.iter().map(|line| line.to_string()).collect()
+}
+
+/// Prints `steps` in order and, when `interactive`, waits for Enter at every
+/// pause. Returns what was typed at the last pause (empty if none).
+pub fn run_steps(steps: Vec<DemoStep>, interactive: bool) -> Result<String> {
+    let mut answer = String::new();
+    for step in steps {
+        let actions = match step {
+            DemoStep::Step { number, title } => {
+                say!("\n📝 STEP {}: {}", number, title);
+                continue;
+            }
+            DemoStep::Pause { points, actions } => {
+                say!("\n⏸️  [PRESENTATION MOMENT]");
+                for point in points {
+                    say!("💡 {}", point);
+                }
+                actions
+            }
+            DemoStep::Final { intro, actions } => {
+                say!("\n⏸️  [FINAL DEMONSTRATION]");
+                say!("🔗 {}", intro);
+                actions
+            }
+        };
+        for action in actions {
+            say!("   {}", action);
+        }
+        answer.clear();
+        if interactive {
+            std::io::stdin().read_line(&mut answer)?;
+        }
+    }
+    Ok(answer)
+}
+
+/// A demo's full list of steps, played back one segment at a time while the
+/// demo does the work in between.
+pub struct Story {
+    steps: VecDeque<DemoStep>,
+    interactive: bool,
+}
+
+impl Story {
+    pub fn new(steps: Vec<DemoStep>, interactive: bool) -> Self {
+        Story { steps: steps.into(), interactive }
+    }
+
+    /// Runs the steps up to the next point where the demo has work to do:
+    /// through the next step title or final pause, or through a pause that
+    /// isn't followed straight away by a step. Returns what was typed.
+    pub fn advance(&mut self) -> Result<String> {
+        let mut segment = Vec::new();
+        while let Some(step) = self.steps.pop_front() {
+            let done = match step {
+                DemoStep::Step { .. } | DemoStep::Final { .. } => true,
+                DemoStep::Pause { .. } => !matches!(self.steps.front(), Some(DemoStep::Step { .. })),
+            };
+            segment.push(step);
+            if done {
+                break;
+            }
+        }
+        run_steps(segment, self.interactive)
+    }
+
+    /// Drops the next step without printing it, for one the demo turned out
+    /// not to need.
+    pub fn skip(&mut self) {
+        self.steps.pop_front();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(story: &Story) -> Vec<&'static str> {
+        story.steps.iter().map(|step| match step {
+            DemoStep::Step { .. } => "step",
+            DemoStep::Pause { .. } => "pause",
+            DemoStep::Final { .. } => "final",
+        }).collect()
+    }
+
+    #[test]
+    fn advance_stops_where_the_demo_acts() {
+        let mut story = Story::new(vec![
+            DemoStep::step(1, "one"),
+            DemoStep::pause(&["point"], &["Press Enter..."]),
+            DemoStep::step(2, "two"),
+            DemoStep::pause(&["point"], &["Press Enter..."]),
+            DemoStep::final_demo("mine", &["Press Enter to mine block..."]),
+        ], false);
+        story.advance().unwrap();
+        assert_eq!(kinds(&story), ["pause", "step", "pause", "final"]);
+        // A pause runs straight into the step it introduces
+        story.advance().unwrap();
+        assert_eq!(kinds(&story), ["pause", "final"]);
+        // ...but not into a final pause
+        story.advance().unwrap();
+        assert_eq!(kinds(&story), ["final"]);
+        story.advance().unwrap();
+        assert!(kinds(&story).is_empty());
+    }
+}
@@ -0,0 +1,416 @@
+use anyhow::{anyhow, bail, ensure, Result};
+use bitcoin::absolute::LockTime;
+use bitcoin::{Address, Amount, Network, OutPoint, Script, Txid};
+use bitcoincore_rpc::json::ListUnspentResultEntry;
+use bitcoincore_rpc::{Auth, Client, RpcApi};
+use serde::Deserialize;
+use serde_json::json;
+use std::path::Path;
+use std::sync::{Arc, Mutex, Once, OnceLock};
+
+use crate::bench::MeteredClient;
+use crate::cli_script::{emit_cli_script, CliCommand};
+use crate::context::{context, NodeContext};
+use crate::consts::{CHANGE_LABEL, COINBASE_MATURITY, DETERMINISTIC_DESCRIPTOR};
+use crate::fmt::{btc, sats};
+use crate::options::Options;
+use crate::sim;
+
+/// Spendable balance [`Node::fund_if_needed`] tops the wallet up to.
+const MIN_FUNDING: Amount = Amount::from_int_btc(10);
+
+/// Most blocks [`Node::fund_if_needed`] mines before giving up (late in a
+/// long regtest chain the subsidy is tiny).
+const MAX_FUNDING_BLOCKS: u64 = 1_000;
+
+/// Wallet-scoped connection to the regtest node shared by all demos.
+pub struct Node {
+    /// Shared so blocking waits can run on tokio's blocking pool.
+    pub rpc: Arc<MeteredClient>,
+    pub opts: Options,
+    pub context: NodeContext,
+    /// UTXOs this run has locked with `lockunspent`, released on drop.
+    locked: Mutex<Vec<OutPoint>>,
+    /// This run's change address, fetched on first use.
+    change_addr: OnceLock<Address>,
+    /// Announces anti-fee-sniping on the first locktime only.
+    fee_snipe_notice: Once,
+}
+
+#[derive(Deserialize)]
+struct BumpFeeResult {
+    txid: Txid,
+}
+
+#[derive(Deserialize)]
+struct LockedUtxo {
+    txid: Txid,
+    vout: u32,
+}
+
+/// Cookie-file auth for the node whose `-datadir` is `path`, found the same way
+/// `bitcoin-cli` does: `<datadir>/<network subdir>/.cookie`.
+pub fn auth_from_datadir(path: &Path, network: Network) -> Result<Auth> {
+    let subdir = match network {
+        Network::Bitcoin => "",
+        Network::Testnet => "testnet3",
+        Network::Testnet4 => "testnet4",
+        Network::Signet => "signet",
+        Network::Regtest => "regtest",
+    };
+    let cookie = path.join(subdir).join(".cookie");
+    ensure!(cookie.is_file(), "no cookie file at {} (is bitcoind running with this -datadir?)", cookie.display());
+    Ok(Auth::CookieFile(cookie))
+}
+
+/// Creates wallet `name` with no keys of its own and imports `descriptor`
+/// (a receive descriptor ending in `/0/*`, no checksum) plus its `/1/*` change
+/// twin as the active ones, so its addresses, and the demo txids built from
+/// them, are the same on every machine that starts from a fresh regtest chain.
+/// `rpc` must already point at `/wallet/<name>`, where the import lands.
+pub fn create_deterministic_wallet(rpc: &Client, name: &str, descriptor: &str) -> Result<()> {
+    ensure!(descriptor.ends_with("/0/*)"), "deterministic descriptor must derive receive addresses at /0/*: {}", descriptor);
+    rpc.create_wallet(name, None, Some(true), None, None)?;
+
+    let change = format!("{}/1/*)", &descriptor[..descriptor.len() - "/0/*)".len()]);
+    let mut requests = Vec::new();
+    for (desc, internal) in [(descriptor, false), (change.as_str(), true)] {
+        let checksum = rpc.get_descriptor_info(desc)?.checksum
+            .ok_or_else(|| anyhow!("getdescriptorinfo returned no checksum for {}", desc))?;
+        requests.push(json!({
+            "desc": format!("{}#{}", desc, checksum),
+            "active": true,
+            "internal": internal,
+            "timestamp": "now",
+        }));
+    }
+    let results: Vec<serde_json::Value> = rpc.call("importdescriptors", &[json!(requests)])?;
+    for result in &results {
+        ensure!(result["success"] == true, "importdescriptors failed: {}", result["error"]);
+    }
+    Ok(())
+}
+
+impl Node {
+    /// Connects to bitcoind, loads (or creates) the demo wallet and returns a
+    /// client bound to that wallet.
+    pub fn connect(opts: &Options) -> Result<Self> {
+        // Connect to bitcoind (without wallet first)
+        let rpc_base = Client::new(&opts.rpc_url, Self::auth(opts)?)?;
+
+        // Check the node is running, and learn what won't change during the run
+        let context = context(&rpc_base)?;
+        say!("✅ Connected to Bitcoin Core ({})", context.chain);
+        say!("   └─ Blocks: {}\n", context.start_height);
+
+        // The demos broadcast real transactions: never touch mainnet by accident
+        if context.chain == Network::Bitcoin {
+            ensure!(opts.force_mainnet, "refusing to run against MAINNET ({}): these demos spend real fees. Point at a regtest node, or pass --force-mainnet if you really mean it", opts.rpc_url);
+            say!("🚨 --force-mainnet: running against MAINNET, every fee below is real money!\n");
+        }
+
+        // Try to load existing wallet or create new one
+        match rpc_base.load_wallet(&opts.wallet) {
+            Ok(_) => say!("💼 Loaded existing wallet"),
+            Err(_) if opts.deterministic => {
+                ensure!(context.chain == Network::Regtest, "--deterministic uses a publicly known key: regtest only");
+                let wallet = Client::new(&format!("{}/wallet/{}", opts.rpc_url, opts.wallet), Self::auth(opts)?)?;
+                if rpc_base.list_wallets()?.contains(&opts.wallet) {
+                    say!("💼 Using existing wallet");
+                } else {
+                    create_deterministic_wallet(&wallet, &opts.wallet, DETERMINISTIC_DESCRIPTOR)?;
+                    say!("💼 Created deterministic wallet (fixed descriptor)");
+                }
+            }
+            Err(_) => {
+                match rpc_base.create_wallet(&opts.wallet, None, None, None, None) {
+                    Ok(_) => say!("💼 Created new wallet"),
+                    Err(_) => say!("💼 Using existing wallet"),
+                }
+            }
+        }
+
+        // Connect to the specific wallet
+        let client = Client::new(&format!("{}/wallet/{}", opts.rpc_url, opts.wallet), Self::auth(opts)?)?;
+        let rpc = MeteredClient::new(client, &opts.wallet, opts.bench, opts.emit_script.is_some());
+        // Whether it was loaded or created here, a replay starts from a new wallet
+        rpc.record(CliCommand { wallet: None, method: "createwallet".to_string(), args: vec![json!(opts.wallet)] });
+        let node = Node { rpc: Arc::new(rpc), opts: opts.clone(), context, locked: Mutex::new(Vec::new()), change_addr: OnceLock::new(), fee_snipe_notice: Once::new() };
+        say!("🧰 {}\n", node.node_capabilities());
+        Ok(node)
+    }
+
+    /// On mainnet, lists the fees a demo is about to pay and, in interactive
+    /// mode, aborts unless the user types `mainnet`. A no-op anywhere else.
+    pub fn confirm_mainnet_fees(&self, fees: &[(&str, Amount)]) -> Result<()> {
+        if self.context.chain != Network::Bitcoin {
+            return Ok(());
+        }
+        say!("💸 About to broadcast MAINNET transactions paying:");
+        if fees.is_empty() {
+            say!("   └─ Fees chosen by the wallet");
+        }
+        for (what, fee) in fees {
+            say!("   ├─ {}: {}", what, btc(*fee));
+        }
+        if !fees.is_empty() {
+            say!("   └─ Total: {}", btc(fees.iter().map(|(_, fee)| *fee).sum()));
+        }
+        if self.opts.interactive {
+            say!("   Type 'mainnet' + Enter to pay these fees, anything else aborts...");
+            ensure!(self.opts.prompt()?.trim() == "mainnet", "aborted: mainnet spend not confirmed");
+        }
+        Ok(())
+    }
+
+    /// Locks a UTXO this run has picked so no other selection (or wallet coin
+    /// selection) can spend it too. Fails if it is already locked.
+    pub fn lock_utxo(&self, outpoint: OutPoint) -> Result<()> {
+        let locked: Vec<LockedUtxo> = self.rpc.call("listlockunspent", &[])?;
+        if locked.iter().any(|l| OutPoint::new(l.txid, l.vout) == outpoint) {
+            bail!("UTXO {} is already locked by another flow", outpoint);
+        }
+        ensure!(self.rpc.lock_unspent(&[outpoint])?, "could not lock UTXO {}", outpoint);
+        self.locked.lock().unwrap().push(outpoint);
+        Ok(())
+    }
+
+    /// Clears `known` out of the mempool: each one no longer there but still
+    /// unconfirmed (replaced, evicted) is abandoned so the wallet can spend its
+    /// inputs again, and a block is mined for any still in the mempool, which
+    /// `abandontransaction` refuses. Fails if any survive that block.
+    pub fn purge_demo_txs(&self, known: &[Txid]) -> Result<()> {
+        let mempool = self.rpc.get_raw_mempool()?;
+        let (pending, gone): (Vec<Txid>, Vec<Txid>) = known.iter().partition(|txid| mempool.contains(txid));
+        for txid in gone {
+            // Confirmed or already abandoned transactions are refused: nothing to do
+            if self.rpc.call::<serde_json::Value>("abandontransaction", &[json!(txid)]).is_ok() {
+                say!("🗑️  Abandoned {}", txid);
+            }
+        }
+        if !pending.is_empty() {
+            ensure!(
+                self.context.chain == Network::Regtest,
+                "{} of this run's transactions are still in the mempool, and only regtest can mine them away", pending.len()
+            );
+            say!("⛏️  Mining a block to flush {} of this run's transactions from the mempool", pending.len());
+            self.rpc.generate_to_address(1, self.change_address()?)?;
+            let mempool = self.rpc.get_raw_mempool()?;
+            let left: Vec<_> = pending.iter().filter(|txid| mempool.contains(txid)).collect();
+            ensure!(left.is_empty(), "still in the mempool after a block: {:?}", left);
+        }
+        say!("🧹 None of this run's {} transaction(s) left in the mempool", known.len());
+        Ok(())
+    }
+
+    /// The address every change output of this run pays to, labeled
+    /// [`CHANGE_LABEL`] so later selections can tell change from funding.
+    pub fn change_address(&self) -> Result<&Address> {
+        if let Some(addr) = self.change_addr.get() {
+            return Ok(addr);
+        }
+        let addr = self.rpc.get_new_address(Some(CHANGE_LABEL), None)?.assume_checked();
+        Ok(self.change_addr.get_or_init(|| addr))
+    }
+
+    /// Whether `script` pays to an address this wallet owns.
+    pub fn is_mine(&self, script: &Script) -> Result<bool> {
+        let Ok(addr) = Address::from_script(script, self.context.chain) else {
+            return Ok(false);
+        };
+        Ok(self.rpc.get_address_info(&addr)?.is_mine.unwrap_or(false))
+    }
+
+    /// Makes sure the wallet can spend at least [`MIN_FUNDING`] before a demo
+    /// starts. On regtest it mines to `funding_addr` until enough coinbase
+    /// outputs have matured, so a fresh wallet works on the first run.
+    pub fn fund_if_needed(&self, funding_addr: &Address) -> Result<()> {
+        let balances = self.rpc.get_balances()?.mine;
+        if balances.trusted >= MIN_FUNDING {
+            say!("💰 Wallet balance: {}\n", btc(balances.trusted));
+            return Ok(());
+        }
+        ensure!(
+            self.context.chain == Network::Regtest,
+            "wallet '{}' can only spend {}: send at least {} to {} and wait for it to confirm",
+            self.opts.wallet, btc(balances.trusted), btc(MIN_FUNDING), funding_addr
+        );
+
+        // A coinbase only becomes spendable COINBASE_MATURITY blocks after its own
+        say!("⛏️  Mining blocks for funding...");
+        let mut mined = u64::from(COINBASE_MATURITY) + 1;
+        self.rpc.generate_to_address(mined, funding_addr)?;
+        loop {
+            let balances = self.rpc.get_balances()?.mine;
+            if balances.trusted >= MIN_FUNDING {
+                say!("   └─ Mined {} blocks, spendable: {}\n", mined, btc(balances.trusted));
+                return Ok(());
+            }
+            say!("   ├─ Mined {} blocks, waiting for maturity... spendable: {} ({} immature)",
+                     mined, btc(balances.trusted), btc(balances.immature));
+            ensure!(mined < MAX_FUNDING_BLOCKS, "still only {} spendable after mining {} blocks", btc(balances.trusted), mined);
+            self.rpc.generate_to_address(1, funding_addr)?;
+            mined += 1;
+        }
+    }
+
+    /// [`Node::fund_if_needed`], then makes sure there are at least `count`
+    /// spendable UTXOs worth `min_amount` each, mining more on regtest. Returns
+    /// them all.
+    pub fn fund_utxos_if_needed(&self, funding_addr: &Address, count: usize, min_amount: Amount) -> Result<Vec<ListUnspentResultEntry>> {
+        self.fund_if_needed(funding_addr)?;
+        let mut mined = 0;
+        loop {
+            let utxos = self.spendable_utxos(min_amount)?;
+            if utxos.len() >= count {
+                return Ok(utxos);
+            }
+            ensure!(
+                self.context.chain == Network::Regtest,
+                "wallet '{}' has {} spendable UTXO(s) of at least {}, this demo needs {}: split its balance into more UTXOs first",
+                self.opts.wallet, utxos.len(), btc(min_amount), count
+            );
+            ensure!(mined < MAX_FUNDING_BLOCKS, "still only {} UTXO(s) of at least {} after mining {} blocks", utxos.len(), btc(min_amount), mined);
+            // Every block matures one more coinbase
+            if mined == 0 {
+                say!("⛏️  Mining until {} UTXOs of at least {} are spendable...", count, btc(min_amount));
+            }
+            self.rpc.generate_to_address(1, funding_addr)?;
+            mined += 1;
+        }
+    }
+
+    /// The first confirmed, mature, unlocked wallet UTXO worth at least `min_amount`.
+    pub fn spendable_utxo(&self, min_amount: Amount) -> Result<ListUnspentResultEntry> {
+        self.spendable_utxos(min_amount)?.into_iter().next()
+            .ok_or_else(|| anyhow!("no confirmed, mature UTXO of at least {} in wallet '{}'", btc(min_amount), self.opts.wallet))
+    }
+
+    /// Every confirmed, mature, unlocked wallet UTXO worth at least
+    /// `min_amount`: never an immature coinbase, which the node would reject
+    /// with `bad-txns-premature-spend-of-coinbase`.
+    pub fn spendable_utxos(&self, min_amount: Amount) -> Result<Vec<ListUnspentResultEntry>> {
+        let mut utxos = Vec::new();
+        for utxo in self.rpc.list_unspent(Some(1), None, None, None, None)? {
+            if utxo.amount >= min_amount && self.is_mature(&utxo)? {
+                utxos.push(utxo);
+            }
+        }
+        Ok(utxos)
+    }
+
+    /// Whether the wallet can spend `utxo` right now: signable, confirmed and,
+    /// if it came from a coinbase, past coinbase maturity.
+    pub fn is_mature(&self, utxo: &ListUnspentResultEntry) -> Result<bool> {
+        if !utxo.spendable || utxo.confirmations < 1 {
+            return Ok(false);
+        }
+        if utxo.confirmations >= COINBASE_MATURITY {
+            return Ok(true);
+        }
+        let tx = self.rpc.get_transaction(&utxo.txid, None)?.transaction()?;
+        Ok(!tx.is_coinbase())
+    }
+
+    /// Current tip height as an `nLockTime`, so the transaction can't be mined
+    /// into a block that re-orgs the tip (anti-fee-sniping, as Core's wallet does).
+    pub fn anti_fee_snipe_locktime(&self) -> Result<LockTime> {
+        let height = self.rpc.get_block_count()?;
+        Ok(LockTime::from_height(height as u32)?)
+    }
+
+    /// The `nLockTime` to put on every hand-built transaction in this run.
+    pub fn lock_time(&self) -> Result<LockTime> {
+        if !self.opts.anti_fee_snipe {
+            return Ok(LockTime::ZERO);
+        }
+        let lock_time = self.anti_fee_snipe_locktime()?;
+        self.fee_snipe_notice.call_once(|| say!("🛡️  Anti-fee-sniping: nLockTime set to the current height ({} now) on every transaction", lock_time));
+        Ok(lock_time)
+    }
+
+    /// [`Self::lock_time`] in the form `createrawtransaction` expects.
+    pub fn raw_lock_time(&self) -> Result<Option<i64>> {
+        Ok(Some(self.lock_time()?.to_consensus_u32().into()))
+    }
+
+    /// RPC credentials: the regtest cookie under `--datadir` if given, otherwise
+    /// the configured user and password.
+    pub fn auth(opts: &Options) -> Result<Auth> {
+        match &opts.datadir {
+            Some(datadir) => {
+                let network = opts.chain.unwrap_or(if opts.force_mainnet { Network::Bitcoin } else { Network::Regtest });
+                auth_from_datadir(datadir, network)
+            }
+            None => Ok(Auth::UserPass(opts.rpc_user.clone(), opts.rpc_pass.clone())),
+        }
+    }
+
+    /// Lets the wallet replace `txid` itself via `bumpfee`, optionally aiming
+    /// for confirmation within `target` blocks. Returns the replacement txid.
+    pub fn wallet_bumpfee(&self, txid: Txid, target: Option<u32>) -> Result<Txid> {
+        let mut options = json!({});
+        if let Some(blocks) = target {
+            options["conf_target"] = json!(blocks);
+        }
+        let result: BumpFeeResult = self.rpc.call("bumpfee", &[json!(txid), options])?;
+        Ok(result.txid)
+    }
+
+    /// Absolute fee the wallet recorded for one of its own transactions.
+    pub fn wallet_fee(&self, txid: Txid) -> Result<Amount> {
+        let tx = self.rpc.get_transaction(&txid, None)?;
+        let fee = tx.fee.ok_or_else(|| anyhow!("wallet has no fee recorded for {}", txid))?;
+        Ok(fee.unsigned_abs())
+    }
+
+    /// Smallest total fee the node will accept for a replacement of `original`
+    /// (BIP125 rules 3 and 4), assuming the replacement is the same size: the
+    /// original's fee plus `incrementalrelayfee` for its vsize.
+    pub fn min_replacement_fee(&self, original: Txid) -> Result<Amount> {
+        let entry = self.rpc.get_mempool_entry(&original)?;
+        Ok(entry.fees.base + sim::incremental_relay_fee(self.context.incremental_fee, entry.vsize as usize))
+    }
+
+    /// Base fee the node itself computed for a transaction in its mempool.
+    pub fn mempool_fee(&self, txid: Txid) -> Result<Amount> {
+        Ok(self.rpc.get_mempool_entry(&txid)?.fees.base)
+    }
+
+    /// Warns when the fee the node computed for `txid` differs from the one
+    /// the demo meant to pay by more than a sat: coin selection or rounding
+    /// drifted from what was printed.
+    pub fn verify_fee(&self, txid: Txid, expected: Amount) -> Result<()> {
+        let actual = self.mempool_fee(txid)?;
+        let drift = actual.to_sat().abs_diff(expected.to_sat());
+        if drift > 1 {
+            say!("⚠️  Fee mismatch for {}: intended {}, node reports {} ({} sat off)", txid, sats(expected), sats(actual), drift);
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Node {
+    /// Releases this run's UTXO locks. Outputs that were spent meanwhile can't
+    /// be unlocked (and no longer need to be), so failures are ignored.
+    fn drop(&mut self) {
+        if let Some(stats) = self.rpc.stats() {
+            say!("\n⏱️  {}", stats);
+        }
+        if let (Some(path), Some(steps)) = (&self.opts.emit_script, self.rpc.script()) {
+            match std::fs::write(path, emit_cli_script(&steps)) {
+                Ok(()) => say!("\n📜 Wrote {} bitcoin-cli commands to {}", steps.len(), path.display()),
+                Err(e) => say!("\n❌ Could not write {}: {}", path.display(), e),
+            }
+        }
+        if self.opts.reset {
+            let known = self.rpc.take_broadcasts();
+            if let Err(e) = self.purge_demo_txs(&known) {
+                say!("\n❌ Could not purge this run's transactions: {}", e);
+            }
+        }
+        for outpoint in self.locked.get_mut().unwrap().drain(..) {
+            let _ = self.rpc.unlock_unspent(&[outpoint]);
+        }
+    }
+}
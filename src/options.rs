@@ -0,0 +1,232 @@
+use anyhow::{bail, Result};
+use bitcoin::{Amount, Network, OutPoint, ScriptBuf, Txid};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::consts::DETERMINISTIC_WALLET;
+use crate::deadline::Method;
+use crate::fees::CONF_TARGET;
+use crate::fmt::Unit;
+use crate::monitor::Timing;
+use crate::p2a::build_anchor;
+
+/// Command-line switches shared by the demos.
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub rpc_url: String,
+    pub rpc_user: String,
+    pub rpc_pass: String,
+    pub wallet: String,
+    /// Pause at each presentation moment and wait for Enter.
+    pub interactive: bool,
+    /// Keep recipient amounts fixed when bumping fees (on by default).
+    pub preserve_payment: bool,
+    /// RBF demo: treat the original as a no-change sweep, so the bump comes out
+    /// of its only output (never below dust) instead of an extra input.
+    pub sweep: bool,
+    /// Lock hand-built transactions to the current tip height.
+    pub anti_fee_snipe: bool,
+    /// Lock the CPFP parent's output behind a relative timelock of this many blocks.
+    pub csv: Option<u16>,
+    /// Write a Graphviz DOT diagram of each demo's transactions to this file.
+    pub dot: Option<PathBuf>,
+    /// Archive every signed transaction as `<txid>.hex` and `<txid>.json` here.
+    pub out_dir: Option<PathBuf>,
+    /// Authenticate with the `.cookie` file in this bitcoind datadir instead of user/pass.
+    pub datadir: Option<PathBuf>,
+    /// Chain whose `.cookie` `datadir` holds: regtest unless given (mainnet with `force_mainnet`).
+    pub chain: Option<Network>,
+    /// Pay for the P2A anchor spend from a P2SH-wrapped segwit output.
+    pub p2sh_fee_input: bool,
+    /// Let `fundrawtransaction` add the anchor spend's fee inputs and change.
+    pub fund_anchor_spend: bool,
+    /// P2A demo: replace the anchor spend with a higher-fee one before mining.
+    pub bump_anchor: bool,
+    /// Target feerate (sat/vB) to use instead of the node's `estimatesmartfee`.
+    pub feerate: Option<f64>,
+    /// RPC URL of a second regtest node, on the same chain but with its own
+    /// mempool, to replay the P2A package into.
+    pub fresh_node: Option<String>,
+    /// Order the P2A parent's outputs by BIP69 instead of appending the anchor last.
+    pub sort_outputs: bool,
+    /// P2A demo: an alternative anchor scriptPubKey to test against relay policy.
+    pub anchor_script: Option<ScriptBuf>,
+    /// P2A demo: size the anchor spend's fee so parent and child reach this feerate (sat/vB).
+    pub package_feerate: Option<f64>,
+    /// Generations of children the CPFP demo builds on the parent (1 = a single child).
+    pub cpfp_depth: usize,
+    /// Let the CPFP child signal RBF, and bump it once it is in the mempool.
+    pub replaceable_child: bool,
+    /// Allow running against a mainnet node (fees still need typed confirmation).
+    pub force_mainnet: bool,
+    /// Accelerate the fully signed transaction in this base64 PSBT file instead of running a demo.
+    pub bump_psbt: Option<PathBuf>,
+    /// `accelerate <txid:vout>`: bump this output's transaction to meet `deadline`.
+    pub accelerate: Option<OutPoint>,
+    /// Blocks within which `accelerate` should get the transaction confirmed.
+    pub deadline: u16,
+    /// How `accelerate` bumps the transaction.
+    pub method: Method,
+    /// Total the stuck transaction's inputs spend, for when its previous
+    /// outputs can't be looked up (pruned node). Taken on trust.
+    pub prevout_amount: Option<Amount>,
+    /// `autopilot <txid:vout>`: spend this output and keep bumping it until it confirms.
+    pub autopilot: Option<OutPoint>,
+    /// Highest feerate (sat/vB) the autopilot may bump to.
+    pub max_feerate: f64,
+    /// Count and time every RPC call and print a summary at the end of the run.
+    pub bench: bool,
+    /// ASCII-only output: no emoji, `[OK]`/`[FAIL]` markers.
+    pub plain: bool,
+    /// Highlight txids, amounts and results with ANSI colors.
+    pub color: bool,
+    /// Write every amount in this unit instead of each message's own choice.
+    pub unit: Option<Unit>,
+    /// Write the demo's acceleration receipt (JSON) to this file instead of printing it.
+    pub receipt: Option<PathBuf>,
+    /// Write the run's state-changing RPC calls to this file as a `bitcoin-cli` shell script.
+    pub emit_script: Option<PathBuf>,
+    /// Use a wallet created from a fixed descriptor, so addresses and txids repeat across machines.
+    pub deterministic: bool,
+    /// When a demo ends, abandon or mine away every transaction it broadcast that is still unconfirmed.
+    pub reset: bool,
+    /// Low-fee payments to broadcast before the RBF, CPFP and P2A demos, as a busy-mempool backdrop.
+    pub flood: usize,
+    /// Poll interval and timeout of every wait for a transaction to confirm.
+    pub timing: Timing,
+    /// `inspect <txid>`: print a report on this transaction instead of running a demo.
+    pub inspect: Option<Txid>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            rpc_url: "http://127.0.0.1:18443".to_string(),
+            rpc_user: "user".to_string(),
+            rpc_pass: "pass".to_string(),
+            wallet: "rbf_demo_wallet".to_string(),
+            interactive: true,
+            preserve_payment: true,
+            sweep: false,
+            anti_fee_snipe: false,
+            csv: None,
+            dot: None,
+            out_dir: None,
+            datadir: None,
+            chain: None,
+            p2sh_fee_input: false,
+            fund_anchor_spend: false,
+            bump_anchor: false,
+            feerate: None,
+            fresh_node: None,
+            sort_outputs: false,
+            anchor_script: None,
+            package_feerate: None,
+            cpfp_depth: 1,
+            replaceable_child: false,
+            force_mainnet: false,
+            bump_psbt: None,
+            accelerate: None,
+            deadline: CONF_TARGET,
+            method: Method::Cpfp,
+            prevout_amount: None,
+            autopilot: None,
+            max_feerate: 100.0,
+            bench: false,
+            plain: false,
+            color: false,
+            unit: None,
+            emit_script: None,
+            receipt: None,
+            deterministic: false,
+            reset: false,
+            flood: 0,
+            timing: Timing::default(),
+            inspect: None,
+        }
+    }
+}
+
+fn value(args: &mut impl Iterator<Item = String>, flag: &str) -> Result<String> {
+    match args.next() {
+        Some(value) => Ok(value),
+        None => bail!("{} needs a value", flag),
+    }
+}
+
+impl Options {
+    pub fn from_args() -> Result<Self> {
+        Self::parse(std::env::args().skip(1))
+    }
+
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self> {
+        let mut opts = Options::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--rpc-url" => opts.rpc_url = value(&mut args, "--rpc-url")?,
+                "--non-interactive" => opts.interactive = false,
+                "--preserve-payment" => opts.preserve_payment = true,
+                "--no-preserve-payment" => opts.preserve_payment = false,
+                "--sweep" => opts.sweep = true,
+                "--anti-fee-snipe" => opts.anti_fee_snipe = true,
+                "--csv" => opts.csv = Some(value(&mut args, "--csv")?.parse()?),
+                "--dot" => opts.dot = Some(value(&mut args, "--dot")?.into()),
+                "--out-dir" => opts.out_dir = Some(value(&mut args, "--out-dir")?.into()),
+                "--datadir" => opts.datadir = Some(value(&mut args, "--datadir")?.into()),
+                "--chain" => opts.chain = Some(Network::from_core_arg(&value(&mut args, "--chain")?)?),
+                "--p2sh-fee-input" => opts.p2sh_fee_input = true,
+                "--fund-anchor-spend" => opts.fund_anchor_spend = true,
+                "--bump-anchor" => opts.bump_anchor = true,
+                "--feerate" => opts.feerate = Some(value(&mut args, "--feerate")?.parse()?),
+                "--fresh-node" => opts.fresh_node = Some(value(&mut args, "--fresh-node")?),
+                "--sort-outputs" => opts.sort_outputs = true,
+                "--anchor-script" => opts.anchor_script = Some(build_anchor(&value(&mut args, "--anchor-script")?)?),
+                "--package-feerate" => opts.package_feerate = Some(value(&mut args, "--package-feerate")?.parse()?),
+                "--cpfp-depth" => opts.cpfp_depth = value(&mut args, "--cpfp-depth")?.parse()?,
+                "--replaceable-child" => opts.replaceable_child = true,
+                "--force-mainnet" => opts.force_mainnet = true,
+                "--bump-psbt" => opts.bump_psbt = Some(value(&mut args, "--bump-psbt")?.into()),
+                "accelerate" => opts.accelerate = Some(value(&mut args, "accelerate")?.parse()?),
+                "--deadline" => opts.deadline = value(&mut args, "--deadline")?.parse()?,
+                "--method" => opts.method = value(&mut args, "--method")?.parse()?,
+                "--prevout-amount" => opts.prevout_amount = Some(Amount::from_sat(value(&mut args, "--prevout-amount")?.parse()?)),
+                "autopilot" => opts.autopilot = Some(value(&mut args, "autopilot")?.parse()?),
+                "--max-feerate" => opts.max_feerate = value(&mut args, "--max-feerate")?.parse()?,
+                "--bench" => opts.bench = true,
+                "--plain" => opts.plain = true,
+                "--color" => opts.color = true,
+                "--unit" => opts.unit = Some(value(&mut args, "--unit")?.parse()?),
+                "--emit-script" => opts.emit_script = Some(value(&mut args, "--emit-script")?.into()),
+                "--receipt" => opts.receipt = Some(value(&mut args, "--receipt")?.into()),
+                "--flood" => opts.flood = value(&mut args, "--flood")?.parse()?,
+                "--reset" => opts.reset = true,
+                "--deterministic" => {
+                    opts.deterministic = true;
+                    opts.wallet = DETERMINISTIC_WALLET.to_string();
+                }
+                "--poll-ms" => opts.timing.poll_interval = Duration::from_millis(value(&mut args, "--poll-ms")?.parse()?),
+                "--timeout-s" => opts.timing.timeout = Duration::from_secs(value(&mut args, "--timeout-s")?.parse()?),
+                "inspect" => opts.inspect = Some(value(&mut args, "inspect")?.parse()?),
+                other => bail!("unknown option: {}", other),
+            }
+        }
+        if opts.cpfp_depth == 0 {
+            bail!("--cpfp-depth must be at least 1");
+        }
+        if opts.timing.poll_interval.is_zero() {
+            bail!("--poll-ms must be at least 1");
+        }
+        Ok(opts)
+    }
+
+    /// Waits for the presenter to press Enter and returns what they typed.
+    /// In non-interactive mode this returns an empty answer immediately.
+    pub fn prompt(&self) -> Result<String> {
+        let mut input = String::new();
+        if self.interactive {
+            std::io::stdin().read_line(&mut input)?;
+        }
+        Ok(input)
+    }
+}
@@ -0,0 +1,116 @@
+//! Every line the demos print goes through [`say!`] and [`render`]. By default
+//! that is the emoji and box-drawing trees as written. `--plain` turns them into
+//! ASCII with `[OK]`/`[FAIL]` markers, for CI logs and terminals that mangle
+//! emoji; `--color` highlights txids, amounts and success or failure.
+
+use std::sync::OnceLock;
+
+/// How [`render`] rewrites output, set once from the command line.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Style {
+    pub plain: bool,
+    pub color: bool,
+}
+
+static STYLE: OnceLock<Style> = OnceLock::new();
+
+/// Picks the style for the rest of the run; later calls are ignored.
+pub fn init(style: Style) {
+    let _ = STYLE.set(style);
+}
+
+/// `println!` through [`render`].
+macro_rules! say {
+    () => {
+        println!()
+    };
+    ($($arg:tt)*) => {
+        println!("{}", $crate::output::render(&format!($($arg)*)))
+    };
+}
+
+/// ASCII stand-ins for the symbols that carry meaning; any other non-ASCII
+/// character (decorative emoji) is dropped in plain mode.
+const PLAIN: [(&str, &str); 13] = [
+    ("✅", "[OK]"),
+    ("❌", "[FAIL]"),
+    ("⚠️", "[WARN]"),
+    ("├─", "|-"),
+    ("└─", "`-"),
+    ("│", "|"),
+    ("→", "->"),
+    ("←", "<-"),
+    ("≥", ">="),
+    ("≤", "<="),
+    ("×", "x"),
+    ("█", "#"),
+    ("…", "..."),
+];
+
+const GREEN: &str = "\x1b[32m";
+const RED: &str = "\x1b[31m";
+const CYAN: &str = "\x1b[36m";
+const YELLOW: &str = "\x1b[33m";
+const RESET: &str = "\x1b[0m";
+
+/// `text` as the current [`Style`] wants it printed.
+pub fn render(text: &str) -> String {
+    let style = STYLE.get().copied().unwrap_or_default();
+    let text = if style.plain { plain(text) } else { text.to_string() };
+    if style.color { colorize(&text) } else { text }
+}
+
+fn plain(text: &str) -> String {
+    let mut text = text.to_string();
+    for (symbol, ascii) in PLAIN {
+        text = text.replace(symbol, ascii);
+    }
+    // Drop the remaining emoji along with the spaces that separated them
+    let mut out = String::with_capacity(text.len());
+    let mut dropped = false;
+    for c in text.chars() {
+        if !c.is_ascii() {
+            dropped = true;
+        } else if c == ' ' && dropped {
+            continue;
+        } else {
+            dropped = false;
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn paint(color: &str, text: &str) -> String {
+    format!("{}{}{}", color, text, RESET)
+}
+
+/// Whether `word` starts with a txid (or block hash): 64 hex digits.
+fn is_hash(word: &str) -> bool {
+    word.len() >= 64 && word.as_bytes()[..64].iter().all(u8::is_ascii_hexdigit)
+}
+
+fn colorize(text: &str) -> String {
+    let words: Vec<&str> = text.split(' ').collect();
+    let mut out = Vec::with_capacity(words.len());
+    let mut i = 0;
+    while i < words.len() {
+        let word = words[i];
+        let unit = words.get(i + 1).copied().unwrap_or_default();
+        let is_amount = word.parse::<f64>().is_ok()
+            && ["BTC", "mBTC", "sats", "sat/vB"].iter().any(|u| unit.trim_end_matches([',', ')', '.', ';']) == *u);
+        if is_amount {
+            out.push(paint(YELLOW, &format!("{} {}", word, unit)));
+            i += 2;
+            continue;
+        }
+        out.push(match word {
+            w if w.contains("✅") || w.contains("[OK]") => paint(GREEN, w),
+            w if w.contains("❌") || w.contains("[FAIL]") => paint(RED, w),
+            w if is_hash(w) => paint(CYAN, w),
+            w => w.to_string(),
+        });
+        i += 1;
+    }
+    out.join(" ")
+}
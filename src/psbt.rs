@@ -0,0 +1,98 @@
+//! `--bump-psbt <file>`: accelerate a transaction that was built and signed
+//! elsewhere and handed over as a base64 PSBT. The replacement (or CPFP child)
+//! goes back through the wallet as a PSBT as well, so inputs it holds no keys
+//! for can still be signed offline.
+
+use anyhow::{bail, Result};
+use bitcoin::consensus::encode::{deserialize, serialize_hex};
+use bitcoin::Transaction;
+use bitcoincore_rpc::RpcApi;
+use std::path::Path;
+
+use crate::consts::FALLBACK_TARGET_FEERATE;
+use crate::fees;
+use crate::fmt::{btc, feerate};
+use crate::node::Node;
+use crate::options::Options;
+use crate::sim;
+
+/// A transaction after the wallet has signed what it can.
+pub enum SignedPsbt {
+    /// Fully signed and finalized: ready to broadcast.
+    Complete(Transaction),
+    /// Still needs signatures this wallet doesn't have (base64 PSBT).
+    Partial(String),
+}
+
+impl Node {
+    /// The transaction inside a base64 PSBT. It must be fully signed: a
+    /// transaction that is stuck has already been broadcast.
+    fn tx_from_psbt(&self, psbt: &str) -> Result<Transaction> {
+        let finalized = self.rpc.finalize_psbt(psbt, Some(true))?;
+        match finalized.hex {
+            Some(hex) if finalized.complete => Ok(deserialize(&hex)?),
+            _ => bail!("the PSBT is not fully signed, so it can't be a broadcast transaction: sign and finalize it first"),
+        }
+    }
+
+    /// Turns `tx` into a PSBT (`converttopsbt`), lets the wallet add UTXO data
+    /// and sign (`walletprocesspsbt`), and finalizes it if that was enough.
+    pub fn sign_psbt(&self, tx: &Transaction) -> Result<SignedPsbt> {
+        let psbt: String = self.rpc.call("converttopsbt", &[serialize_hex(tx).into()])?;
+        let processed = self.rpc.wallet_process_psbt(&psbt, Some(true), None, None)?;
+        if !processed.complete {
+            return Ok(SignedPsbt::Partial(processed.psbt));
+        }
+        Ok(SignedPsbt::Complete(self.tx_from_psbt(&processed.psbt)?))
+    }
+
+    /// First output of `tx` this wallet owns: the change to bump from, or the
+    /// output a child can spend.
+    pub fn own_output(&self, tx: &Transaction) -> Result<usize> {
+        for (vout, output) in tx.output.iter().enumerate() {
+            if self.is_mine(&output.script_pubkey)? {
+                return Ok(vout);
+            }
+        }
+        bail!("no output of {} belongs to wallet '{}', so there is nothing to bump from", tx.compute_txid(), self.opts.wallet)
+    }
+}
+
+/// Reads the stuck transaction from the PSBT at `path` and accelerates it:
+/// by replacement if it signals RBF, with a CPFP child otherwise. If the
+/// wallet can't sign the result alone, it is written next to `path` as
+/// `<name>.bumped.psbt` for offline signing.
+pub fn run_bump(opts: &Options, path: &Path) -> Result<()> {
+    let node = Node::connect(opts)?;
+    let original = node.tx_from_psbt(std::fs::read_to_string(path)?.trim())?;
+    let txid = original.compute_txid();
+    say!("📥 Stuck transaction from {}: {}", path.display(), txid);
+    if node.rpc.get_mempool_entry(&txid).is_err() {
+        say!("   ├─ Not in the mempool yet, broadcasting it first");
+        node.broadcast(&original, None)?;
+    }
+    let fee = node.mempool_fee(txid)?;
+    say!("   ├─ Fee: {} ({})", btc(fee), feerate(sim::feerate(fee, original.vsize())));
+    say!("   └─ Signals RBF: {}\n", original.is_explicitly_rbf());
+
+    let target = fees::target_feerate(&*node.fee_estimator()).unwrap_or(FALLBACK_TARGET_FEERATE);
+    let (bumped, bumped_fee) = if original.is_explicitly_rbf() {
+        node.unsigned_replacement(&original, node.own_output(&original)?, fee, target)?
+    } else {
+        node.unsigned_child(&original, node.own_output(&original)?, fee, target)?
+    };
+
+    match node.sign_psbt(&bumped)? {
+        SignedPsbt::Complete(tx) => {
+            node.confirm_mainnet_fees(&[("Bump fee", bumped_fee)])?;
+            say!("📡 Broadcast {}", node.broadcast(&tx, None)?);
+        }
+        SignedPsbt::Partial(psbt) => {
+            let out = path.with_extension("bumped.psbt");
+            std::fs::write(&out, psbt + "\n")?;
+            say!("✍️  The wallet can't sign every input: the bumped PSBT is in {}", out.display());
+            say!("   └─ Sign it offline, then `finalizepsbt` and `sendrawtransaction`");
+        }
+    }
+    Ok(())
+}
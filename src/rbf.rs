@@ -1,192 +1,1383 @@
 #![allow(unused_doc_comments)]
-use anyhow::Result;
-use bitcoin::Amount;
-use bitcoincore_rpc::{Auth, Client, RpcApi};
+use anyhow::{anyhow, bail, ensure, Result};
+use bitcoin::consensus::encode::serialize_hex;
+use bitcoin::{Address, Amount, BlockHash, OutPoint, Transaction, TxIn, TxOut, Txid};
+use bitcoincore_rpc::json::{FundRawTransactionOptions, GetMempoolEntryResult, ListUnspentResultEntry};
+use bitcoincore_rpc::RpcApi;
+use serde::Deserialize;
+use serde_json::json;
 use std::collections::HashMap;
+use std::fmt;
 
-pub async fn run_demo() -> Result<()> {
-    println!("🚀 RBF Demo - REAL Replace-by-Fee\n");
+use crate::builder::unsigned_copy;
+use crate::capabilities::Feature;
+use crate::consts::{FALLBACK_TARGET_FEERATE, SEQUENCE_FINAL, SEQUENCE_RBF};
+use crate::deadline::Method;
+use crate::dot;
+use crate::dump;
+use crate::errors::reject_reason;
+use crate::fees;
+use crate::fmt::{btc, feerate, sats};
+use crate::mempool::mempool_delta;
+use crate::narrative::{DemoStep, Story};
+use crate::node::Node;
+use crate::options::Options;
+use crate::sim;
 
-    /////////////////////
-    /// Initial Setup ///
-    /////////////////////
-    // Connect to regtest bitcoind (without wallet first)
-    let rpc_base = Client::new("http://127.0.0.1:18443", Auth::UserPass("user".to_string(), "pass".to_string()))?;
-    
-    // Check regtest is running
-    let blockchain_info = rpc_base.get_blockchain_info()?;
-    println!("✅ Connected to Bitcoin Core (regtest)");
-    println!("   └─ Chain: {}, Blocks: {}\n", blockchain_info.chain, blockchain_info.blocks);
-
-    // Try to load existing wallet or create new one
-    let wallet_name = "rbf_demo_wallet";
-    match rpc_base.load_wallet(wallet_name) {
-        Ok(_) => println!("💼 Loaded existing wallet"),
-        Err(_) => {
-            match rpc_base.create_wallet(wallet_name, None, None, None, None) {
-                Ok(_) => println!("💼 Created new wallet"),
-                Err(_) => println!("💼 Using existing wallet"),
+/// What happened to the original and its replacement by the end of the demo.
+pub struct RbfOutcome {
+    pub original_txid: Txid,
+    pub replacement_txid: Txid,
+    /// sat/vB of the original and of the replacement.
+    pub original_feerate: f64,
+    pub replacement_feerate: f64,
+    pub original_fee: Amount,
+    pub replacement_fee: Amount,
+    /// Hash and height of the block mined (or waited for) at the end.
+    pub block: (BlockHash, u64),
+    pub original_evicted: bool,
+    pub original_confirmed: bool,
+    pub replacement_confirmed: bool,
+}
+
+impl fmt::Display for RbfOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "RBF: original {} ({}, {}) → replacement {} ({}), bumped from {} to {} ({:+.0}%)",
+            self.original_txid,
+            if self.original_evicted { "evicted" } else { "NOT evicted" },
+            if self.original_confirmed { "confirmed" } else { "unconfirmed" },
+            self.replacement_txid,
+            if self.replacement_confirmed { "confirmed" } else { "unconfirmed" },
+            feerate(self.original_feerate),
+            feerate(self.replacement_feerate),
+            sim::feerate_improvement(self.original_feerate, self.replacement_feerate),
+        )
+    }
+}
+
+/// What the node did with a replacement of a transaction that doesn't signal BIP125.
+pub struct RbfRejectionDemo {
+    pub original_txid: Txid,
+    /// Whether the node replaces regardless of signalling (`mempoolfullrbf`,
+    /// the default since Core 28).
+    pub full_rbf: bool,
+    /// The replacement, if the node took it.
+    pub replacement_txid: Option<Txid>,
+    /// bitcoind's reject reason otherwise (`txn-mempool-conflict`).
+    pub rejection: Option<String>,
+}
+
+impl fmt::Display for RbfRejectionDemo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let policy = if self.full_rbf { "full-RBF" } else { "opt-in RBF only" };
+        match (&self.replacement_txid, &self.rejection) {
+            (Some(replacement), _) => write!(f, "non-signalling {} replaced by {} ({} node)", self.original_txid, replacement, policy),
+            (None, Some(reason)) => write!(f, "non-signalling {} NOT replaced: {} ({} node)", self.original_txid, reason, policy),
+            (None, None) => write!(f, "non-signalling {}: replacement not attempted ({} node)", self.original_txid, policy),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SpendingPrevout {
+    /// Absent when nothing in the mempool spends the outpoint.
+    spendingtxid: Option<Txid>,
+}
+
+#[derive(Deserialize)]
+struct MempoolPolicy {
+    /// Reported by Core 26 and later.
+    fullrbf: Option<bool>,
+}
+
+/// Paid on top of the node's minimum replacement fee, to absorb a replacement
+/// that ends up a little larger than the original.
+pub const REPLACEMENT_MARGIN: Amount = Amount::from_sat(1_000);
+
+/// Why a replacement's fee is too low for BIP125.
+#[derive(Debug)]
+pub enum RbfError {
+    /// Rule 3: the replacement must pay at least the original's absolute fee.
+    FeeNotIncreased { original: Amount, replacement: Amount },
+    /// Rule 4: it must also pay the incremental relay fee for its own vsize,
+    /// so every vbyte it adds over the original costs extra.
+    InsufficientForBandwidth { paid: Amount, required: Amount, added_vbytes: i64 },
+    /// The replacement must also raise the feerate of its whole ancestor
+    /// package, or miners have no reason to prefer it.
+    ClusterNotImproved { original_rate: f64, replacement_rate: f64, ancestors: usize },
+    /// The replacement left out some of the original's inputs (e.g. wallet
+    /// coin selection picked others), so it may not conflict with the original
+    /// at all and the two could both end up in the mempool.
+    InputsDropped { missing: Vec<OutPoint> },
+}
+
+impl fmt::Display for RbfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RbfError::FeeNotIncreased { original, replacement } => {
+                write!(f, "BIP125 rule 3: replacement fee {} is below the original's {}", btc(*replacement), btc(*original))
             }
+            RbfError::InsufficientForBandwidth { paid, required, added_vbytes } => write!(
+                f,
+                "BIP125 rule 4: replacement pays {} but needs {} to cover its bandwidth ({:+} vB vs the original)",
+                btc(*paid), btc(*required), added_vbytes
+            ),
+            RbfError::ClusterNotImproved { original_rate, replacement_rate, ancestors } => write!(
+                f,
+                "replacement's package with its {} unconfirmed ancestor(s) would pay {}, not above the original's {}",
+                ancestors, feerate(*replacement_rate), feerate(*original_rate)
+            ),
+            RbfError::InputsDropped { missing } => write!(
+                f,
+                "replacement does not spend {} of the original's input(s) ({}): it must keep them all and may only add confirmed ones",
+                missing.len(),
+                missing.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ")
+            ),
         }
     }
+}
 
-    // Connect to the specific wallet
-    let rpc = Client::new(&format!("http://127.0.0.1:18443/wallet/{}", wallet_name), Auth::UserPass("user".to_string(), "pass".to_string()))?;
+impl std::error::Error for RbfError {}
+
+/// Checks BIP125 rules 3 and 4 for replacing a transaction paying
+/// `original_fee` over `original_vsize` vB with one paying `replacement_fee`
+/// over `replacement_vsize` vB, on a node whose `incrementalrelayfee` is
+/// `incremental` (per kvB).
+pub fn check_replacement_fee(original_fee: Amount, original_vsize: usize, replacement_fee: Amount, replacement_vsize: usize, incremental: Amount) -> Result<(), RbfError> {
+    if replacement_fee < original_fee {
+        return Err(RbfError::FeeNotIncreased { original: original_fee, replacement: replacement_fee });
+    }
+    let required = original_fee + sim::incremental_relay_fee(incremental, replacement_vsize);
+    if replacement_fee < required {
+        return Err(RbfError::InsufficientForBandwidth {
+            paid: replacement_fee,
+            required,
+            added_vbytes: replacement_vsize as i64 - original_vsize as i64,
+        });
+    }
+    Ok(())
+}
+
+/// Checks that `replacement` spends every input of `original`, adding inputs
+/// at most (which BIP125 rule 2 allows as long as they are confirmed). A
+/// replacement whose inputs were picked by wallet coin selection can silently
+/// drop the original's, and then it replaces nothing.
+pub fn assert_same_inputs(original: &Transaction, replacement: &Transaction) -> Result<(), RbfError> {
+    let missing: Vec<OutPoint> = original
+        .input
+        .iter()
+        .map(|input| input.previous_output)
+        .filter(|outpoint| !replacement.input.iter().any(|input| input.previous_output == *outpoint))
+        .collect();
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(RbfError::InputsDropped { missing })
+    }
+}
+
+/// Where the extra fee for a payment-preserving replacement comes from.
+enum BumpFunding {
+    /// Shrink the original's change output to this value.
+    Change(Amount),
+    /// Add this confirmed wallet UTXO and send what's left of it back as change.
+    ExtraInput { outpoint: OutPoint, change: Amount },
+}
+
+/// Funds a fee increase of `bump` without touching any recipient output: from
+/// the original's change if it is large enough, otherwise from an extra input.
+fn fund_bump(change: Option<Amount>, candidates: &[ListUnspentResultEntry], bump: Amount) -> Result<BumpFunding> {
+    if let Some(rest) = change.and_then(|c| c.checked_sub(bump)) {
+        return Ok(BumpFunding::Change(rest));
+    }
+    candidates
+        .iter()
+        .find(|u| u.amount > bump)
+        .map(|u| BumpFunding::ExtraInput {
+            outpoint: OutPoint::new(u.txid, u.vout),
+            change: u.amount - bump,
+        })
+        .ok_or_else(|| anyhow!(
+            "cannot add {} of fee without lowering the payment: no change output or spare confirmed UTXO covers it (pass --no-preserve-payment to allow that)",
+            btc(bump)
+        ))
+}
+
+pub async fn run_demo(opts: &Options) -> Result<Option<RbfOutcome>> {
+    say!("🚀 RBF Demo - REAL Replace-by-Fee\n");
+    let mut story = Story::new(vec![
+        DemoStep::step(1, "Creating original transaction"),
+        DemoStep::pause(
+            &["Original transaction is in mempool with LOW fee"],
+            &["Press Enter to create REPLACEMENT transaction manually,", "or type 'w' + Enter to let the wallet bump it (bumpfee)..."],
+        ),
+        DemoStep::step(2, "Creating REPLACEMENT transaction"),
+        DemoStep::final_demo("Let's mine a block to see which transaction gets confirmed...", &["Press Enter to mine block..."]),
+    ], opts.interactive);
+
+    /////////////////////
+    /// Initial Setup ///
+    /////////////////////
+    let node = Node::connect(opts)?;
+    let rpc = &node.rpc;
+    node.print_fee_histogram()?;
 
     // Get addresses
     let target_addr = rpc.get_new_address(None, None)?.assume_checked();
     let funding_addr = rpc.get_new_address(None, None)?.assume_checked();
     
     // Fund wallet if needed
-    let balance = rpc.get_balance(None, None)?;
-    if balance.to_btc() < 10.0 {
-        println!("⛏️  Mining blocks for funding...");
-        rpc.generate_to_address(101, &funding_addr)?;
-        let new_balance = rpc.get_balance(None, None)?;
-        println!("   └─ Balance: {} BTC\n", new_balance);
-    } else {
-        println!("💰 Wallet balance: {} BTC\n", balance);
-    }
+    node.fund_if_needed(&funding_addr)?;
+    node.flood_if_requested()?;
 
     // Get a specific UTXO to spend (for true RBF)
-    let unspent = rpc.list_unspent(None, None, None, None, None)?;
-    if unspent.is_empty() || unspent[0].amount.to_btc() < 1.0 {
-        println!("❌ Need larger UTXOs, mining more blocks...");
-        rpc.generate_to_address(100, &funding_addr)?;
-        return Ok(());
-    }
-
-    let utxo = &unspent[0];
-    println!("🎯 Using UTXO: {}:{} ({} BTC)", utxo.txid, utxo.vout, utxo.amount);
+    let utxo = node.spendable_utxo(Amount::ONE_BTC)?;
+    say!("🎯 Using UTXO: {}:{} ({})", utxo.txid, utxo.vout, btc(utxo.amount));
+    node.lock_utxo(OutPoint::new(utxo.txid, utxo.vout))?;
 
     // Calculate reasonable amounts based on UTXO size
-    let utxo_amount = utxo.amount.to_btc();
-    let fee1 = 0.0001; // Low fee
-    let fee2 = 0.001;  // High fee (10x higher)
-    let send_amount1 = utxo_amount - fee1;
-    let send_amount2 = utxo_amount - fee2;
+    let fee1 = Amount::from_sat(10_000); // Low fee
+    let send_amount1 = utxo.amount - fee1;
 
-    println!("💡 Will send {} BTC (fee: {}), then {} BTC (fee: {})\n", 
-             send_amount1, fee1, send_amount2, fee2);
+    say!("💡 Will send {} (fee: {}), then replace it with just enough fee to win",
+             btc(send_amount1), btc(fee1));
+    node.confirm_mainnet_fees(&[("Original fee", fee1)])?;
 
     /////////////////////////
     /// First Transaction ///
     /////////////////////////
-    println!("📝 STEP 1: Creating original transaction");
-    println!("   ├─ UTXO: {}:{}", utxo.txid, utxo.vout);
-    println!("   ├─ Send: {} BTC", send_amount1);
-    println!("   ├─ Fee: {} BTC (low)", fee1);
-    println!("   └─ RBF: ENABLED\n");
-
-    // Create inputs with RBF sequence
-    let inputs = vec![bitcoincore_rpc::json::CreateRawTransactionInput {
-        txid: utxo.txid,
-        vout: utxo.vout,
-        sequence: Some(0xfffffffd), // RBF enabled
-    }];
-
-    // Create outputs
-    let mut outputs = HashMap::new();
-    outputs.insert(target_addr.to_string(), Amount::from_btc(send_amount1)?);
+    story.advance()?;
+    say!("   ├─ UTXO: {}:{}", utxo.txid, utxo.vout);
+    say!("   ├─ Send: {}", btc(send_amount1));
+    say!("   ├─ Fee: {} (low)", btc(fee1));
+    say!("   └─ RBF: ENABLED\n");
 
-    // Create raw transaction
-    let raw_tx1 = rpc.create_raw_transaction(&inputs, &outputs, None, Some(true))?;
-    let signed_tx1 = rpc.sign_raw_transaction_with_wallet(&raw_tx1, None, None)?;
+    // Spend the UTXO with an RBF-signalling sequence
+    let utxo_outpoint = OutPoint::new(utxo.txid, utxo.vout);
+    let tx1 = node.tx_builder()?
+        .input(utxo_outpoint, SEQUENCE_RBF) // RBF enabled
+        .pay(send_amount1, &target_addr)
+        .build();
+    let signed_tx1 = node.sign_with_wallet(&tx1)?;
 
     // Broadcast original transaction
-    let original_txid = rpc.send_raw_transaction(&signed_tx1.hex)?;
-    println!("✅ Original TX broadcasted: {}", original_txid);
+    let before = rpc.get_raw_mempool()?;
+    let original_txid = node.broadcast(&signed_tx1.hex, None)?;
+    say!("✅ Original TX broadcasted: {}", original_txid);
+    say!("   └─ Size: {}", sim::size_breakdown(&signed_tx1.transaction()?));
+    node.verify_fee(original_txid, fee1)?;
 
     // Check mempool
     let mempool = rpc.get_raw_mempool()?;
-    println!("🔍 Mempool: {} transactions", mempool.len());
-    println!("   └─ Contains original: {}\n", mempool.contains(&original_txid));
+    say!("🔍 Mempool: {} transactions", mempool.len());
+    say!("{}\n", mempool_delta(&before, &mempool));
+
+    // Ask the node how much a replacement must pay, then add a small margin,
+    // or pay more if that's what the target feerate takes
+    let min_fee = node.min_replacement_fee(original_txid)?;
+    let original_vsize = signed_tx1.transaction()?.vsize();
+    let original_weight = signed_tx1.transaction()?.weight().to_wu() as usize;
+    let target_fee = fees::target_feerate(&*node.fee_estimator())
+        .map_or(Amount::ZERO, |rate| sim::fee_from_feerate(original_weight, rate));
+    let fee2 = (min_fee + REPLACEMENT_MARGIN).max(target_fee);
+    // A sweep has no change: its one output pays every fee, down to the dust limit
+    let preserve_payment = opts.preserve_payment && !opts.sweep;
+    if opts.sweep {
+        let max_fee = sim::max_sweep_fee(utxo.amount, &target_addr.script_pubkey());
+        ensure!(
+            fee2 <= max_fee,
+            "--sweep: a {} fee would leave the only output below dust (at most {} fits in {})",
+            btc(fee2), btc(max_fee), btc(utxo.amount)
+        );
+        say!("🧹 --sweep: no change output, so the bump lowers the payment");
+        say!("   ├─ {} → {}", btc(send_amount1), btc(utxo.amount - fee2));
+        say!("   └─ Highest fee this sweep can pay: {}\n", btc(max_fee));
+    }
+    let send_amount2 = if preserve_payment { send_amount1 } else { utxo.amount - fee2 };
+    if fee2 == target_fee {
+        say!("🎯 Node says a replacement needs at least {}: we'll pay {} to reach the target feerate\n",
+                 btc(min_fee), btc(fee2));
+    } else {
+        say!("🎯 Node says a replacement needs at least {}: we'll pay {} (+{} margin)\n",
+                 btc(min_fee), btc(fee2), sats(REPLACEMENT_MARGIN));
+    }
+
+    if !preserve_payment && !opts.sweep {
+        say!("⚠️  ⚠️  WARNING: --no-preserve-payment is set!");
+        say!("   ├─ The replacement pays the recipient {} instead of {}", btc(send_amount2), btc(send_amount1));
+        say!("   └─ The higher fee comes out of the PAYMENT, not from change\n");
+    }
+    node.confirm_mainnet_fees(&[("Replacement fee", fee2)])?;
+    node.print_cost_preview(OutPoint::new(original_txid, 0), Method::Rbf)?;
 
     // Pause for presentation
-    println!("⏸️  [PRESENTATION MOMENT]");
-    println!("💡 Original transaction is in mempool with LOW fee");
-    println!("💡 It spends UTXO: {}:{}", utxo.txid, utxo.vout);
-    println!("   Press Enter to create REPLACEMENT transaction...");
-    let mut input = String::new();
-    std::io::stdin().read_line(&mut input)?;
+    let input = story.advance()?;
+    let wallet_native = input.trim().eq_ignore_ascii_case("w");
+    let before_replacement = rpc.get_raw_mempool()?;
 
     //////////////////////////
     /// Second Transaction ///
     //////////////////////////
-    println!("📝 STEP 2: Creating REPLACEMENT transaction");
-    println!("   ├─ SAME UTXO: {}:{}", utxo.txid, utxo.vout);
-    println!("   ├─ Send: {} BTC", send_amount2);
-    println!("   ├─ Fee: {} BTC (10x higher)", fee2);
-    println!("   └─ RBF: ENABLED\n");
+    let (replacement_txid, replacement_tx, replacement_fee) = if wallet_native {
+        say!("   ├─ Asking the WALLET to bump the fee (bumpfee)");
+        say!("   ├─ Original: {}", original_txid);
+        say!("   ├─ Wallet picks the new fee and coin selection");
+        say!("   └─ RBF: ENABLED\n");
+
+        let replacement_txid = node.wallet_bumpfee(original_txid, None)?;
+        say!("✅ Wallet replacement TX broadcasted: {}", replacement_txid);
+        let replacement_tx = rpc.get_transaction(&replacement_txid, None)?.transaction()?;
+        assert_same_inputs(&tx1, &replacement_tx)?;
+        say!("   └─ Size: {}", sim::size_breakdown(&replacement_tx));
+
+        // Compare what the wallet chose with our hand-rolled numbers
+        let wallet_fee = node.wallet_fee(replacement_txid)?;
+        let manual_fee = fee2;
+        say!("\n⚖️  Wallet-native vs manual bump:");
+        say!("   ├─ Wallet (bumpfee) fee: {}", btc(wallet_fee));
+        say!("   ├─ Manual replacement fee: {}", btc(manual_fee));
+        if wallet_fee < manual_fee {
+            say!("   └─ Wallet paid {} LESS than our manual bump", sats(manual_fee - wallet_fee));
+        } else {
+            say!("   └─ Wallet paid {} MORE than our manual bump", sats(wallet_fee - manual_fee));
+        }
+        (replacement_txid, replacement_tx, wallet_fee)
+    } else {
+        say!("   ├─ SAME UTXO: {}:{}", utxo.txid, utxo.vout);
+        say!("   ├─ Send: {}", btc(send_amount2));
+        say!("   ├─ Fee: {} (just enough to win)", btc(fee2));
+        say!("   └─ RBF: ENABLED\n");
 
-    // Create replacement with SAME inputs but higher fee
-    let mut replacement_outputs = HashMap::new();
-    replacement_outputs.insert(target_addr.to_string(), Amount::from_btc(send_amount2)?);
+        // Create replacement with SAME inputs but higher fee
+        let mut replacement = node.tx_builder()?
+            .input(utxo_outpoint, SEQUENCE_RBF)
+            .pay(send_amount2, &target_addr);
 
-    let raw_tx2 = rpc.create_raw_transaction(&inputs, &replacement_outputs, None, Some(true))?;
-    let signed_tx2 = rpc.sign_raw_transaction_with_wallet(&raw_tx2, None, None)?;
+        if preserve_payment {
+            // The original has no change output, so the bump must come from an extra
+            // input. BIP125 rule 2: any added input must already be confirmed.
+            let bump = fee2 - fee1;
+            let spare = node.spendable_utxos(Amount::ZERO)?;
+            if spare.is_empty() {
+                let unconfirmed = rpc.list_unspent(Some(0), None, None, None, None)?.len();
+                bail!(
+                    "only unconfirmed UTXOs ({}) are available to fund the bump, and BIP125 rule 2 forbids adding unconfirmed inputs to a replacement: mine a block and retry",
+                    unconfirmed
+                );
+            }
+            match fund_bump(None, &spare, bump)? {
+                BumpFunding::Change(change) => {
+                    replacement = replacement.pay(change, node.change_address()?);
+                }
+                BumpFunding::ExtraInput { outpoint, change } => {
+                    node.lock_utxo(outpoint)?;
+                    say!("💡 Payment preserved at {}", btc(send_amount2));
+                    say!("   ├─ Extra input {} funds the bump", outpoint);
+                    say!("   └─ Change back to wallet: {}\n", btc(change));
+                    replacement = replacement.input(outpoint, SEQUENCE_RBF).pay(change, node.change_address()?);
+                }
+            }
+        }
+
+        let signed_tx2 = node.sign_with_wallet(&replacement.build())?;
+        assert_same_inputs(&tx1, &signed_tx2.transaction()?)?;
 
-    // Broadcast replacement transaction
-    let replacement_txid = rpc.send_raw_transaction(&signed_tx2.hex)?;
-    println!("✅ Replacement TX broadcasted: {}", replacement_txid);
+        // BIP125 rule 4: the replacement must also pay for its own bandwidth, including any
+        // vbytes it adds (the extra input and change output when preserving the payment)
+        let replacement_vsize = signed_tx2.transaction()?.vsize();
+        let incremental = node.context.incremental_fee;
+        let required = fee1 + sim::incremental_relay_fee(incremental, replacement_vsize);
+        say!("📏 BIP125 rule 4: fee must be ≥ {} (original fee + {} × {} vB)",
+                 btc(required), feerate(incremental.to_sat() as f64 / 1000.0), replacement_vsize);
+        if replacement_vsize > original_vsize {
+            let added = replacement_vsize - original_vsize;
+            say!("   ├─ Replacement is {} vB LARGER than the original: +{} for the added bandwidth",
+                     added, sats(sim::incremental_relay_fee(incremental, added)));
+            let same_size_fee = fee1 + sim::incremental_relay_fee(incremental, original_vsize);
+            if let Err(e) = check_replacement_fee(fee1, original_vsize, same_size_fee, replacement_vsize, incremental) {
+                say!("   ├─ A same-size bump ({}) would be rejected: {}", btc(same_size_fee), e);
+            }
+        }
+        check_replacement_fee(fee1, original_vsize, fee2, replacement_vsize, incremental)?;
+        say!("   └─ Our fee: {} ✅\n", btc(fee2));
+
+        // Preview with the offline simulator against the fee estimator's target
+        let original_rate = sim::feerate(fee1, original_vsize);
+        let replacement_rate = sim::feerate(fee2, replacement_vsize);
+        say!("🔮 Preview (sim): {} → {}", feerate(original_rate), feerate(replacement_rate));
+        if let Some(target) = fees::target_feerate(&*node.fee_estimator()) {
+            let needed = sim::rbf_replacement_fee(fee1, replacement_vsize, target, incremental);
+            let verdict = if fee2 >= needed { "✅" } else { "⚠️  short of it" };
+            say!("   └─ Replacement fee for {}: {} (ours {} {})\n", feerate(target), sats(needed), sats(fee2), verdict);
+        }
+
+        // Broadcast replacement transaction
+        let replacement_txid = node.broadcast(&signed_tx2.hex, None)?;
+        say!("✅ Replacement TX broadcasted: {}", replacement_txid);
+        say!("   └─ Size: {}", sim::size_breakdown(&signed_tx2.transaction()?));
+        node.verify_fee(replacement_txid, fee2)?;
+        (replacement_txid, signed_tx2.transaction()?, fee2)
+    };
+
+    let original_feerate = sim::feerate(fee1, original_vsize);
+    let replacement_feerate = sim::feerate(replacement_fee, replacement_tx.vsize());
+    let txs = [
+        (original_txid, signed_tx1.transaction()?, fee1),
+        (replacement_txid, replacement_tx, replacement_fee),
+    ];
+    dot::write_dot(opts, &txs)?;
+    dump::write_txs(opts, &txs)?;
 
     // Check mempool after replacement
-    println!("\n🔍 Mempool Status (After RBF):");
     let final_mempool = rpc.get_raw_mempool()?;
-    println!("   ├─ Total transactions: {}", final_mempool.len());
-    println!("   ├─ Original TX present: {}", if final_mempool.contains(&original_txid) { "❌ STILL THERE" } else { "✅ EVICTED!" });
-    println!("   └─ Replacement TX present: {}", if final_mempool.contains(&replacement_txid) { "✅ YES" } else { "❌ NO" });
+    // Only the two transactions this demo is about: others may have come, gone or been mined meanwhile
+    let delta = mempool_delta(&before_replacement, &final_mempool).only(&[original_txid, replacement_txid]);
+    say!("\n🔍 Mempool Status (After RBF): {} transactions", final_mempool.len());
+    say!("{}", delta);
+    let original_evicted = delta.removed.contains(&original_txid);
+    if delta.added.contains(&replacement_txid) {
+        say!("💰 Replacement fee (node's view): {}", btc(node.mempool_fee(replacement_txid)?));
+        node.print_rank("replacement", replacement_feerate)?;
+    }
 
     // Show the magic of RBF!
-    if !final_mempool.contains(&original_txid) && final_mempool.contains(&replacement_txid) {
-        println!("\n🎉 RBF SUCCESS!");
-        println!("✅ Original transaction was REPLACED!");
-        println!("✅ Same UTXO, higher fee wins!");
-        println!("✅ Miners will prefer the replacement!");
+    if original_evicted && delta.added.contains(&replacement_txid) {
+        say!("\n🎉 RBF SUCCESS!");
+        say!("✅ Original transaction was REPLACED!");
+        say!("✅ Same UTXO, higher fee wins: bumped from {} to {} ({:+.0}%)",
+                 feerate(original_feerate), feerate(replacement_feerate),
+                 sim::feerate_improvement(original_feerate, replacement_feerate));
+        say!("✅ Miners will prefer the replacement!");
     } else {
-        println!("\n⚠️  RBF may not have worked as expected");
-        println!("   (Both transactions might be in mempool)");
+        say!("\n⚠️  RBF may not have worked as expected");
+        say!("   (Both transactions might be in mempool)");
     }
 
     // Mine a block to see final result
-    println!("\n⏸️  [FINAL DEMONSTRATION]");
-    println!("🔗 Let's mine a block to see which transaction gets confirmed...");
-    println!("   Press Enter to mine block...");
-    input.clear();
-    std::io::stdin().read_line(&mut input)?;
-
-    println!("⛏️  Mining block...");
-    let blocks = rpc.generate_to_address(1, &funding_addr)?;
+    story.advance()?;
+
+    let block_hash = node.confirm(node.confirmer(&funding_addr), &[replacement_txid]).await?;
     
     // Check what actually got confirmed
-    let block = rpc.get_block(&blocks[0])?;
-    println!("\n📦 Block {} mined!", blocks[0]);
-    println!("   ├─ Transactions in block: {}", block.txdata.len());
+    let block = rpc.get_block(&block_hash)?;
+    let block_height = rpc.get_block_header_info(&block_hash)?.height as u64;
+    say!("\n📦 Block {} mined!", block_hash);
+    say!("   ├─ Transactions in block: {}", block.txdata.len());
     
     let orig_confirmed = block.txdata.iter().any(|tx| tx.compute_txid().to_string() == original_txid.to_string());
     let replacement_confirmed = block.txdata.iter().any(|tx| tx.compute_txid().to_string() == replacement_txid.to_string());
     
-    println!("   ├─ Original confirmed: {}", if orig_confirmed { "✅ YES" } else { "❌ NO" });
-    println!("   └─ Replacement confirmed: {}", if replacement_confirmed { "✅ YES" } else { "❌ NO" });
+    say!("   ├─ Original confirmed: {}", if orig_confirmed { "✅ YES" } else { "❌ NO" });
+    say!("   └─ Replacement confirmed: {}", if replacement_confirmed { "✅ YES" } else { "❌ NO" });
+    if replacement_confirmed {
+        node.print_block_stats(block_hash, replacement_feerate, 1);
+    }
 
     // Final verdict
-    println!("\n🎉 RBF DEMO COMPLETE!");
+    say!("\n🎉 RBF DEMO COMPLETE!");
     if replacement_confirmed && !orig_confirmed {
-        println!("🏆 PERFECT! Only the replacement transaction was mined!");
-        println!("💡 The original was completely replaced - this is TRUE RBF!");
+        say!("🏆 PERFECT! Only the replacement transaction was mined!");
+        say!("💡 The original was completely replaced - this is TRUE RBF!");
+        let cost = sim::rbf_cost_analysis(fee1, replacement_fee);
+        say!("\n💸 What the acceleration cost:");
+        say!("   ├─ Paid: {} (the replacement's fee, the only one mined)", btc(cost.paid));
+        say!("   ├─ Would have paid: {} (the original's fee, never charged)", btc(cost.would_have_paid));
+        say!("   └─ Extra for confirming sooner: {}", btc(cost.delta));
+        say!("💡 You never pay both fees: only relay bandwidth was spent on the evicted original");
     } else if orig_confirmed && !replacement_confirmed {
-        println!("🤔 Original was mined instead - RBF didn't work as expected");
+        say!("🤔 Original was mined instead - RBF didn't work as expected");
+    } else {
+        say!("🤷 Unexpected result - check the implementation");
+    }
+
+    say!("\n📚 What we demonstrated:");
+    say!("   ├─ Created transaction spending specific UTXO");
+    say!("   ├─ Enabled RBF with sequence < 0xfffffffe");
+    if wallet_native {
+        say!("   ├─ Let the wallet build the replacement with bumpfee");
     } else {
-        println!("🤷 Unexpected result - check the implementation");
+        say!("   ├─ Created replacement spending SAME UTXO with higher fee");
+    }
+    say!("   ├─ Showed original was evicted from mempool");
+    say!("   └─ Confirmed only replacement was mined");
+    say!("\n💡 This is REAL Replace-by-Fee in action!");
+
+    Ok(Some(RbfOutcome {
+        original_txid,
+        replacement_txid,
+        original_feerate,
+        replacement_feerate,
+        original_fee: fee1,
+        replacement_fee,
+        block: (block_hash, block_height),
+        original_evicted,
+        original_confirmed: orig_confirmed,
+        replacement_confirmed,
+    }))
+}
+
+impl Node {
+    /// Same inputs and outputs as `original`, with the fee raised to `target`
+    /// sat/vB (and BIP125's minimum) out of output `vout`. Unsigned, with the
+    /// fee it pays.
+    pub fn unsigned_replacement(&self, original: &Transaction, vout: usize, fee: Amount, target: f64) -> Result<(Transaction, Amount)> {
+        let new_fee = sim::rbf_replacement_fee(fee, original.vsize(), target, self.context.incremental_fee);
+        let mut replacement = unsigned_copy(original);
+        let output = replacement.output.get_mut(vout).ok_or_else(|| anyhow!("{} has no output {}", original.compute_txid(), vout))?;
+        output.value = output
+            .value
+            .checked_sub(new_fee - fee)
+            .filter(|value| *value >= output.script_pubkey.minimal_non_dust())
+            .ok_or_else(|| anyhow!("output {} ({}) can't cover a {} bump", vout, btc(output.value), btc(new_fee - fee)))?;
+        say!("🔄 RBF replacement: fee {} → {}, taken from output {}", btc(fee), btc(new_fee), vout);
+        Ok((replacement, new_fee))
+    }
+
+    /// Lets the wallet fund `outputs` at `rate` sat/vB (coin selection, change
+    /// and BIP125 signalling), broadcasts the result, then bumps it with
+    /// `bumpfee`. Returns the replacement's txid.
+    pub fn fund_and_bump(&self, outputs: &HashMap<String, Amount>, rate: f64) -> Result<Txid> {
+        let raw = self.rpc.create_raw_transaction_hex(&[], outputs, self.raw_lock_time()?, Some(true))?;
+        let options = FundRawTransactionOptions {
+            fee_rate: Some(Amount::from_sat((rate * 1000.0).round() as u64)), // per kvB
+            replaceable: Some(true),
+            ..Default::default()
+        };
+        let funded = self.rpc.fund_raw_transaction(raw, Some(&options), None)?;
+        let signed = self.rpc.sign_raw_transaction_with_wallet(&funded.hex, None, None)?;
+        let original_txid = self.broadcast(&signed.hex, None)?;
+
+        say!("✅ Wallet-funded original broadcasted: {}", original_txid);
+        say!("   ├─ Fee: {} ({})", btc(funded.fee), feerate(rate));
+        say!("   └─ Change output index: {}\n", funded.change_position);
+
+        // bumpfee does its own coin selection: make sure it kept the original's inputs
+        let replacement_txid = self.wallet_bumpfee(original_txid, None)?;
+        let replacement = self.rpc.get_transaction(&replacement_txid, None)?.transaction()?;
+        assert_same_inputs(&signed.transaction()?, &replacement)?;
+        Ok(replacement_txid)
     }
+}
+
+pub async fn run_funded_demo(opts: &Options) -> Result<()> {
+    say!("🚀 RBF Demo - Wallet coin selection (fundrawtransaction + bumpfee)\n");
+
+    let node = Node::connect(opts)?;
+    let rpc = &node.rpc;
+
+    // Get addresses
+    let target_addr = rpc.get_new_address(None, None)?.assume_checked();
+    let funding_addr = rpc.get_new_address(None, None)?.assume_checked();
+
+    // Fund wallet if needed
+    node.fund_if_needed(&funding_addr)?;
+
+    let send_amount = Amount::from_btc(1.0)?;
+    let rate = 2.0;
+    node.confirm_mainnet_fees(&[])?;
+    say!("📝 Paying {} at {}, wallet picks inputs and change", btc(send_amount), feerate(rate));
+
+    let mut outputs = HashMap::new();
+    outputs.insert(target_addr.to_string(), send_amount);
+    let before = rpc.get_raw_mempool()?;
+    let replacement_txid = node.fund_and_bump(&outputs, rate)?;
 
-    println!("\n📚 What we demonstrated:");
-    println!("   ├─ Created transaction spending specific UTXO");
-    println!("   ├─ Enabled RBF with sequence < 0xfffffffe");
-    println!("   ├─ Created replacement spending SAME UTXO with higher fee");
-    println!("   ├─ Showed original was evicted from mempool");
-    println!("   └─ Confirmed only replacement was mined");
-    println!("\n💡 This is REAL Replace-by-Fee in action!");
+    let replacement_fee = node.wallet_fee(replacement_txid)?;
+    say!("✅ Wallet bumped the fee: {}", replacement_txid);
+    say!("   └─ New fee: {}", btc(replacement_fee));
+    say!("🔍 Mempool delta (the original came and went):");
+    say!("{}", mempool_delta(&before, &rpc.get_raw_mempool()?));
+
+    let block_hash = node.confirm(node.confirmer(&funding_addr), &[replacement_txid]).await?;
+    let block = rpc.get_block(&block_hash)?;
+    let confirmed = block.txdata.iter().any(|tx| tx.compute_txid() == replacement_txid);
+    say!("📦 Block {} mined!", block_hash);
+    say!("   └─ Replacement confirmed: {}", if confirmed { "✅ YES" } else { "❌ NO" });
 
     Ok(())
-}
\ No newline at end of file
+}
+
+impl Node {
+    /// Replaces `original` with a copy that leaves out output `drop_index` ("cancel
+    /// one payment in a batch"). The freed value goes entirely to fee when
+    /// `extra_fee_from_dropped` is set; otherwise a new change output takes it,
+    /// minus the smallest fee increase BIP125 allows.
+    pub fn rbf_drop_output(&self, original: Txid, drop_index: usize, extra_fee_from_dropped: bool) -> Result<Txid> {
+        let tx = self.rpc.get_transaction(&original, None)?.transaction()?;
+        ensure!(tx.is_explicitly_rbf(), "{} does not signal BIP125 replaceability", original);
+        ensure!(drop_index < tx.output.len(), "{} has no output {}", original, drop_index);
+        ensure!(
+            !extra_fee_from_dropped || tx.output.len() > 1,
+            "dropping the only output leaves nothing to pay: route it to change instead"
+        );
+        let original_fee = self.wallet_fee(original)?;
+
+        let mut replacement = unsigned_copy(&tx);
+        let dropped = replacement.output.remove(drop_index);
+        if !extra_fee_from_dropped {
+            let change_addr = self.change_address()?;
+            replacement.output.push(TxOut { value: dropped.value, script_pubkey: change_addr.script_pubkey() });
+        }
+
+        // BIP125 rules 3 and 4: pay at least the original fee plus relay for our own size
+        let vsize = self.sign_with_wallet(&replacement)?.transaction()?.vsize() as u64;
+        let min_fee = original_fee + sim::incremental_relay_fee(self.context.incremental_fee, vsize as usize);
+        let replacement_fee = if extra_fee_from_dropped {
+            original_fee + dropped.value
+        } else {
+            let bump = min_fee - original_fee;
+            let change_output = replacement.output.last_mut().expect("change output pushed above");
+            let change = dropped.value.checked_sub(bump)
+                .filter(|change| *change >= change_output.script_pubkey.minimal_non_dust())
+                .ok_or_else(|| anyhow!("dropped output ({}) can't cover the {} BIP125 bump", btc(dropped.value), sats(bump)))?;
+            change_output.value = change;
+            min_fee
+        };
+        check_replacement_fee(original_fee, tx.vsize(), replacement_fee, vsize as usize, self.context.incremental_fee)?;
+
+        say!("✂️  Dropping output #{} ({}) from {}", drop_index, btc(dropped.value), original);
+        say!("   ├─ Freed value goes to: {}", if extra_fee_from_dropped { "FEE" } else { "change" });
+        say!("   ├─ Fee: {} → {}", btc(original_fee), btc(replacement_fee));
+        say!("   └─ BIP125 minimum: {} ✅\n", btc(min_fee));
+
+        let signed = self.sign_with_wallet(&replacement)?;
+        self.broadcast(&signed.hex, None)
+    }
+
+    /// Fees and vsize of every unconfirmed ancestor of `txid` (`getmempoolancestors`).
+    fn mempool_ancestors(&self, txid: Txid) -> Result<(usize, Amount, u64)> {
+        let ancestors: HashMap<Txid, GetMempoolEntryResult> = self.rpc.call("getmempoolancestors", &[json!(txid), json!(true)])?;
+        let fees = ancestors.values().map(|entry| entry.fees.base).sum();
+        let vsize = ancestors.values().map(|entry| entry.vsize).sum();
+        Ok((ancestors.len(), fees, vsize))
+    }
+
+    /// Replaces `original`, which spends unconfirmed parents, with a copy paying
+    /// `new_fee` out of the wallet's change. Besides BIP125, the replacement
+    /// must raise the feerate of the package it forms with those ancestors.
+    pub fn replace_in_cluster(&self, original: Txid, new_fee: Amount) -> Result<Txid> {
+        let tx = self.rpc.get_transaction(&original, None)?.transaction()?;
+        ensure!(tx.is_explicitly_rbf(), "{} does not signal BIP125 replaceability", original);
+        let original_fee = self.mempool_fee(original)?;
+        let (count, ancestor_fees, ancestor_vsize) = self.mempool_ancestors(original)?;
+
+        // This run's change if the original pays it, otherwise any output the wallet owns
+        let change_script = self.change_address()?.script_pubkey();
+        let vout = match tx.output.iter().position(|output| output.script_pubkey == change_script) {
+            Some(vout) => vout,
+            None => self.own_output(&tx)?,
+        };
+        let mut replacement = unsigned_copy(&tx);
+        let bump = new_fee.checked_sub(original_fee).unwrap_or(Amount::ZERO);
+        let output = &mut replacement.output[vout];
+        output.value = output.value.checked_sub(bump)
+            .filter(|value| *value >= output.script_pubkey.minimal_non_dust())
+            .ok_or_else(|| anyhow!("output {} ({}) can't cover a {} bump", vout, btc(output.value), btc(bump)))?;
+        let signed = self.sign_with_wallet(&replacement)?;
+        let vsize = signed.transaction()?.vsize();
+
+        let original_rate = sim::package_feerate(ancestor_fees, ancestor_vsize as usize, original_fee, tx.vsize());
+        let replacement_rate = sim::package_feerate(ancestor_fees, ancestor_vsize as usize, new_fee, vsize);
+        say!("🕸️  {} has {} unconfirmed ancestor(s): {} over {} vB", original, count, btc(ancestor_fees), ancestor_vsize);
+        say!("   ├─ Package feerate now: {}", feerate(original_rate));
+        say!("   └─ With the replacement: {}\n", feerate(replacement_rate));
+        if replacement_rate <= original_rate {
+            return Err(RbfError::ClusterNotImproved { original_rate, replacement_rate, ancestors: count }.into());
+        }
+        check_replacement_fee(original_fee, tx.vsize(), new_fee, vsize, self.context.incremental_fee)?;
+        self.broadcast(&signed.hex, None)
+    }
+
+    /// Two low-fee parents, each from its own confirmed UTXO, and an RBF
+    /// payment spending an output of both: a replacement target that isn't a
+    /// top-level transaction. Returns the payment's txid.
+    pub fn broadcast_two_parent_cluster(&self, parent_fee: Amount, fee: Amount, recipient: &Address) -> Result<Txid> {
+        let mut cluster = self.tx_builder()?;
+        let mut total = Amount::ZERO;
+        let mut parents = 0;
+        for utxo in self.rpc.list_unspent(Some(1), None, None, None, None)? {
+            if parents == 2 {
+                break;
+            }
+            if utxo.amount.to_btc() < 1.0 || !self.is_mature(&utxo)? {
+                continue;
+            }
+            self.lock_utxo(OutPoint::new(utxo.txid, utxo.vout))?;
+            let parent = self.tx_builder()?
+                .input(OutPoint::new(utxo.txid, utxo.vout), SEQUENCE_RBF)
+                .pay(utxo.amount - parent_fee, self.change_address()?)
+                .build();
+            let parent_txid = self.broadcast(&self.sign_with_wallet(&parent)?.hex, None)?;
+            say!("   ├─ Parent {}: {} (fee: {})", parents + 1, parent_txid, btc(parent_fee));
+            cluster = cluster.input(OutPoint::new(parent_txid, 0), SEQUENCE_RBF);
+            total += utxo.amount - parent_fee;
+            parents += 1;
+        }
+        ensure!(parents == 2, "need 2 mature confirmed UTXOs of at least 1 BTC, found {}", parents);
+
+        let payment = Amount::from_btc(1.0)?;
+        let cluster = cluster.pay(payment, recipient).pay(total - payment - fee, self.change_address()?).build();
+        let txid = self.broadcast(&self.sign_with_wallet(&cluster)?.hex, None)?;
+        say!("   └─ Payment spending both: {} (fee: {})", txid, btc(fee));
+        Ok(txid)
+    }
+
+    /// Cancels the unconfirmed payment `original`: double-spends all of its
+    /// inputs into a single output to `refund_addr`, paying `new_fee`, which
+    /// must satisfy BIP125 against the original.
+    pub fn rbf_cancel(&self, original: Txid, refund_addr: &Address, new_fee: Amount) -> Result<Txid> {
+        let tx = self.rpc.get_transaction(&original, None)?.transaction()?;
+        ensure!(tx.is_explicitly_rbf(), "{} does not signal BIP125 replaceability", original);
+        let original_fee = self.wallet_fee(original)?;
+        let total_in = tx.output.iter().map(|output| output.value).sum::<Amount>() + original_fee;
+        let refund = total_in.checked_sub(new_fee)
+            .filter(|refund| *refund >= refund_addr.script_pubkey().minimal_non_dust())
+            .ok_or_else(|| anyhow!("inputs ({}) can't cover a {} cancellation fee", btc(total_in), btc(new_fee)))?;
+
+        let mut cancel = self.tx_builder()?;
+        for input in &tx.input {
+            cancel = cancel.input(input.previous_output, SEQUENCE_RBF);
+        }
+        let signed = self.sign_with_wallet(&cancel.pay(refund, refund_addr).build())?;
+        check_replacement_fee(original_fee, tx.vsize(), new_fee, signed.transaction()?.vsize(), self.context.incremental_fee)?;
+
+        say!("🚫 Cancelling {}: all {} input(s) go back to {}", original, tx.input.len(), refund_addr);
+        say!("   ├─ Refund: {}", btc(refund));
+        say!("   └─ Fee: {} → {}\n", btc(original_fee), btc(new_fee));
+        self.broadcast(&signed.hex, None)
+    }
+
+    /// Broadcasts a payment that does NOT signal BIP125 (every sequence
+    /// `0xffffffff`), then tries to replace it with a higher fee. An opt-in
+    /// node refuses with `txn-mempool-conflict`; a full-RBF node takes it.
+    pub fn demonstrate_rbf_rejection(&self) -> Result<RbfRejectionDemo> {
+        let policy: MempoolPolicy = self.rpc.call("getmempoolinfo", &[])?;
+        let full_rbf = policy.fullrbf.unwrap_or(false);
+        let utxo = self.spendable_utxo(Amount::ONE_BTC)?;
+        let outpoint = OutPoint::new(utxo.txid, utxo.vout);
+        self.lock_utxo(outpoint)?;
+
+        let payee = self.rpc.get_new_address(None, None)?.assume_checked();
+        let fee = Amount::from_sat(1_000);
+        let original = self.tx_builder()?.input(outpoint, SEQUENCE_FINAL).pay(utxo.amount - fee, &payee).build();
+        let original_txid = self.broadcast(&self.sign_with_wallet(&original)?.hex, None)?;
+        say!("📤 Original {} broadcast with sequence {:#010x}: no RBF signal", original_txid, SEQUENCE_FINAL.0);
+        say!("   └─ Node policy: {}\n", if full_rbf { "full-RBF (mempoolfullrbf=1)" } else { "opt-in RBF only" });
+
+        let new_fee = self.min_replacement_fee(original_txid)? + REPLACEMENT_MARGIN;
+        let replacement = self.tx_builder()?.input(outpoint, SEQUENCE_FINAL).pay(utxo.amount - new_fee, &payee).build();
+        let signed = self.sign_with_wallet(&replacement)?;
+        say!("🔁 Replacing it with fee {} → {}...", btc(fee), btc(new_fee));
+        let (replacement_txid, rejection) = match self.rpc.send_raw_transaction(&signed.hex) {
+            Ok(txid) => (Some(txid), None),
+            Err(e) => match reject_reason(&e) {
+                Some(reason) => (None, Some(reason)),
+                None => return Err(e.into()),
+            },
+        };
+        Ok(RbfRejectionDemo { original_txid, full_rbf, replacement_txid, rejection })
+    }
+
+    /// Replaces the unconfirmed payment `original` with one that also pays
+    /// `amount` to `new_recipient`: every original output stays, and the new
+    /// payment plus the rise to `new_fee` come out of this run's change, or
+    /// out of an extra confirmed input when the change is too small.
+    /// `new_fee` must satisfy BIP125 against the original.
+    pub fn rbf_add_recipient(&self, original: Txid, new_recipient: &Address, amount: Amount, new_fee: Amount) -> Result<Txid> {
+        let tx = self.rpc.get_transaction(&original, None)?.transaction()?;
+        ensure!(tx.is_explicitly_rbf(), "{} does not signal BIP125 replaceability", original);
+        let original_fee = self.wallet_fee(original)?;
+        let extra = amount + new_fee.checked_sub(original_fee).unwrap_or(Amount::ZERO);
+
+        let change_script = self.change_address()?.script_pubkey();
+        let change_vout = tx.output.iter().position(|output| output.script_pubkey == change_script);
+        let mut replacement = unsigned_copy(&tx);
+        let funding = fund_bump(change_vout.map(|vout| tx.output[vout].value), &self.spendable_utxos(Amount::ZERO)?, extra)
+            .map_err(|_| anyhow!("neither the change nor a spare confirmed UTXO covers {} (new payment plus fee increase)", btc(extra)))?;
+        let funded_from = match funding {
+            BumpFunding::Change(change) => {
+                let vout = change_vout.expect("change funding needs a change output");
+                replacement.output[vout].value = change;
+                format!("change ({} left)", btc(change))
+            }
+            BumpFunding::ExtraInput { outpoint, change } => {
+                // BIP125 rule 2: fund_bump only offers confirmed UTXOs
+                self.lock_utxo(outpoint)?;
+                replacement.input.push(TxIn { previous_output: outpoint, sequence: SEQUENCE_RBF, ..Default::default() });
+                match change_vout {
+                    Some(vout) => replacement.output[vout].value += change,
+                    None => replacement.output.push(TxOut { value: change, script_pubkey: change_script }),
+                }
+                format!("extra input {}", outpoint)
+            }
+        };
+        replacement.output.push(TxOut { value: amount, script_pubkey: new_recipient.script_pubkey() });
+
+        let signed = self.sign_with_wallet(&replacement)?;
+        let signed_tx = signed.transaction()?;
+        assert_same_inputs(&tx, &signed_tx)?;
+        check_replacement_fee(original_fee, tx.vsize(), new_fee, signed_tx.vsize(), self.context.incremental_fee)?;
+
+        say!("➕ Adding {} to {} in {}", btc(amount), new_recipient, original);
+        say!("   ├─ Outputs: {} → {}, inputs: {} → {}", tx.output.len(), signed_tx.output.len(), tx.input.len(), signed_tx.input.len());
+        say!("   ├─ Funded from: {}", funded_from);
+        say!("   └─ Fee: {} → {}\n", btc(original_fee), btc(new_fee));
+        self.broadcast(&signed.hex, None)
+    }
+
+    /// Replaces `parent` with a copy paying `new_fee` and rebuilds its
+    /// unconfirmed `child` to spend the replacement, since the original child
+    /// is evicted along with the parent. `new_fee` must cover both evicted
+    /// fees (BIP125 rule 3); the child keeps its own fee. The pair goes out as
+    /// one package when the node takes it, one after the other otherwise.
+    /// Returns the new parent and child txids.
+    pub fn rbf_parent_preserving_child(&self, parent: Txid, child: Txid, new_fee: Amount) -> Result<(Txid, Txid)> {
+        let parent_tx = self.rpc.get_transaction(&parent, None)?.transaction()?;
+        let child_tx = self.rpc.get_transaction(&child, None)?.transaction()?;
+        ensure!(parent_tx.is_explicitly_rbf(), "{} does not signal BIP125 replaceability", parent);
+        let spent: Vec<u32> = child_tx.input.iter()
+            .filter(|input| input.previous_output.txid == parent)
+            .map(|input| input.previous_output.vout)
+            .collect();
+        ensure!(!spent.is_empty(), "{} does not spend any output of {}", child, parent);
+        let parent_fee = self.mempool_fee(parent)?;
+        let child_fee = self.mempool_fee(child)?;
+
+        // The bump comes out of a wallet output the child doesn't spend, so
+        // the child's inputs keep their values
+        let mut vout = None;
+        for (i, output) in parent_tx.output.iter().enumerate() {
+            if !spent.contains(&(i as u32)) && self.is_mine(&output.script_pubkey)? {
+                vout = Some(i);
+                break;
+            }
+        }
+        let vout = vout.ok_or_else(|| anyhow!("{} has no wallet output besides the ones {} spends: bump the child instead", parent, child))?;
+        let mut replacement = unsigned_copy(&parent_tx);
+        let bump = new_fee.checked_sub(parent_fee).unwrap_or(Amount::ZERO);
+        let output = &mut replacement.output[vout];
+        output.value = output.value.checked_sub(bump)
+            .filter(|value| *value >= output.script_pubkey.minimal_non_dust())
+            .ok_or_else(|| anyhow!("output {} ({}) can't cover a {} bump", vout, btc(output.value), btc(bump)))?;
+        let new_parent = self.sign_with_wallet(&replacement)?.transaction()?;
+        let new_parent_txid = new_parent.compute_txid();
+        check_replacement_fee(parent_fee + child_fee, parent_tx.vsize() + child_tx.vsize(), new_fee, new_parent.vsize(), self.context.incremental_fee)?;
+
+        let mut rebuilt = unsigned_copy(&child_tx);
+        for input in &mut rebuilt.input {
+            if input.previous_output.txid == parent {
+                input.previous_output.txid = new_parent_txid;
+            }
+        }
+        let new_child = self.sign_child_with_wallet(&rebuilt, &new_parent)?.transaction()?;
+
+        say!("🔁 Replacing parent {} and carrying child {} over", parent, child);
+        say!("   ├─ Parent fee: {} → {} (covers the evicted child's {})", btc(parent_fee), btc(new_fee), btc(child_fee));
+        say!("   ├─ Child keeps its fee, now spending {}:{:?}", new_parent_txid, spent);
+
+        let package = json!([serialize_hex(&new_parent), serialize_hex(&new_child)]);
+        if self.node_capabilities().has(Feature::SubmitPackage) {
+            match self.rpc.call::<serde_json::Value>("submitpackage", &[package]) {
+                Ok(result) if result["package_msg"] == "success" => {
+                    say!("   └─ Submitted as one package ✅\n");
+                    return Ok((new_parent_txid, new_child.compute_txid()));
+                }
+                Ok(result) => say!("   ├─ submitpackage refused the pair ({}), broadcasting one by one", result["package_msg"]),
+                Err(e) => say!("   ├─ submitpackage failed ({}), broadcasting one by one", e),
+            }
+        }
+        let parent_txid = self.broadcast(&new_parent, None)?;
+        let child_txid = self.broadcast(&new_child, None)?;
+        say!("   └─ Broadcast parent, then child ✅\n");
+        Ok((parent_txid, child_txid))
+    }
+
+    /// The mempool transaction spending `outpoint` (`gettxspendingprevout`),
+    /// plus its descendants, with the fees and vsize a replacement has to
+    /// outbid. Empty if nothing in the mempool spends it.
+    fn conflict_set(&self, outpoint: OutPoint) -> Result<(Vec<Txid>, Amount, u64)> {
+        self.node_capabilities().require(Feature::TxSpendingPrevout)?;
+        let prevouts = json!([{ "txid": outpoint.txid, "vout": outpoint.vout }]);
+        let spending: Vec<SpendingPrevout> = self.rpc.call("gettxspendingprevout", &[prevouts])?;
+        // The mempool never holds two spenders of the same outpoint
+        let Some(spender) = spending.into_iter().next().and_then(|s| s.spendingtxid) else {
+            return Ok((Vec::new(), Amount::ZERO, 0));
+        };
+        // The entry's descendant totals include the spender itself
+        let entry = self.rpc.get_mempool_entry(&spender)?;
+        let descendants: Vec<Txid> = self.rpc.call("getmempooldescendants", &[json!(spender)])?;
+        let txids = std::iter::once(spender).chain(descendants).collect();
+        Ok((txids, entry.fees.descendant, entry.descendant_size))
+    }
+
+    /// Spends the wallet's `outpoint` to `desired_dest`, paying `fee`, no
+    /// matter what in the mempool already spends it: the replacement has to
+    /// outbid the whole conflict set, the spender and its descendants, at
+    /// once (BIP125 rules 3 and 4 against their combined fees). Reports which
+    /// txids it evicted.
+    pub fn resolve_conflict(&self, outpoint: OutPoint, desired_dest: &Address, fee: Amount) -> Result<Txid> {
+        let prev = self.rpc.get_transaction(&outpoint.txid, None)?.transaction()?;
+        let value = prev.output.get(outpoint.vout as usize)
+            .ok_or_else(|| anyhow!("{} has no output {}", outpoint.txid, outpoint.vout))?
+            .value;
+        let (conflicts, conflict_fees, conflict_vsize) = self.conflict_set(outpoint)?;
+        ensure!(!conflicts.is_empty(), "nothing in the mempool spends {}: there is no conflict to resolve", outpoint);
+        let amount = value.checked_sub(fee)
+            .filter(|amount| *amount >= desired_dest.script_pubkey().minimal_non_dust())
+            .ok_or_else(|| anyhow!("{} ({}) can't cover a {} fee", outpoint, btc(value), btc(fee)))?;
+
+        let replacement = self.tx_builder()?.input(outpoint, SEQUENCE_RBF).pay(amount, desired_dest).build();
+        let signed = self.sign_with_wallet(&replacement)?;
+        check_replacement_fee(conflict_fees, conflict_vsize as usize, fee, signed.transaction()?.vsize(), self.context.incremental_fee)?;
+
+        say!("⚔️  {} is already spent in the mempool: {} conflicting transaction(s)", outpoint, conflicts.len());
+        say!("   ├─ Conflict set pays: {} over {} vB", btc(conflict_fees), conflict_vsize);
+        say!("   └─ Replacement pays: {} to {}\n", btc(fee), desired_dest);
+        let before = self.rpc.get_raw_mempool()?;
+        let txid = self.broadcast(&signed.hex, None)?;
+        let evicted: Vec<Txid> = mempool_delta(&before, &self.rpc.get_raw_mempool()?).only(&conflicts).removed;
+        say!("🧹 Evicted {} of {} conflicting transaction(s):", evicted.len(), conflicts.len());
+        for (i, conflict) in conflicts.iter().enumerate() {
+            let branch = if i + 1 == conflicts.len() { "└─" } else { "├─" };
+            say!("   {} {} {}", branch, conflict, if evicted.contains(conflict) { "✅ evicted" } else { "❌ still in the mempool" });
+        }
+        Ok(txid)
+    }
+}
+
+pub async fn run_drop_output_demo(opts: &Options) -> Result<()> {
+    say!("🚀 RBF Demo - Cancelling one payment in a batch\n");
+    let mut story = Story::new(vec![
+        DemoStep::step(1, "Broadcasting a batch payment"),
+        DemoStep::pause(
+            &["One of the payments must be cancelled before it confirms"],
+            &["Press Enter to give its value to the FEE,", "or type 'c' + Enter to send it back as change (minimum BIP125 bump)..."],
+        ),
+        DemoStep::step(2, "Replacing the batch without that payment"),
+    ], opts.interactive);
+
+    let node = Node::connect(opts)?;
+    let rpc = &node.rpc;
+
+    // Get addresses
+    let funding_addr = rpc.get_new_address(None, None)?.assume_checked();
+    let recipients: Vec<_> = (0..3)
+        .map(|_| Ok(rpc.get_new_address(None, None)?.assume_checked()))
+        .collect::<Result<_>>()?;
+
+    // Fund wallet if needed
+    node.fund_if_needed(&funding_addr)?;
+    node.confirm_mainnet_fees(&[])?;
+
+    // === STEP 1: A wallet-funded batch payment that signals RBF ===
+    let mut outputs = HashMap::new();
+    for (i, addr) in recipients.iter().enumerate() {
+        outputs.insert(addr.to_string(), Amount::from_sat(10_000_000 * (i as u64 + 1)));
+    }
+    let raw = rpc.create_raw_transaction_hex(&[], &outputs, node.raw_lock_time()?, Some(true))?;
+    let options = FundRawTransactionOptions { replaceable: Some(true), ..Default::default() };
+    let funded = rpc.fund_raw_transaction(raw, Some(&options), None)?;
+    let signed = rpc.sign_raw_transaction_with_wallet(&funded.hex, None, None)?;
+    let batch_txid = node.broadcast(&signed.hex, None)?;
+
+    let batch = signed.transaction()?;
+    story.advance()?;
+    say!("   ├─ Batch: {}", batch_txid);
+    for (vout, output) in batch.output.iter().enumerate() {
+        let role = if vout as i32 == funded.change_position { "change" } else { "payment" };
+        say!("   ├─ #{}: {} ({})", vout, btc(output.value), role);
+    }
+    say!("   └─ Fee: {}\n", btc(funded.fee));
+
+    // Cancel the first payment that isn't the change output
+    let drop_index = (0..batch.output.len())
+        .find(|vout| *vout as i32 != funded.change_position)
+        .ok_or_else(|| anyhow!("batch has no payment outputs"))?;
+
+    say!("🎯 Cancelling payment #{}", drop_index);
+    let answer = story.advance()?;
+    let to_fee = !answer.trim().eq_ignore_ascii_case("c");
+
+    // === STEP 2: Replace the batch without that payment ===
+    let before = rpc.get_raw_mempool()?;
+    let replacement_txid = node.rbf_drop_output(batch_txid, drop_index, to_fee)?;
+    say!("✅ Replacement TX broadcasted: {}", replacement_txid);
+
+    say!("\n🔍 Mempool Status (After RBF):");
+    say!("{}", mempool_delta(&before, &rpc.get_raw_mempool()?));
+
+    let block_hash = node.confirm(node.confirmer(&funding_addr), &[replacement_txid]).await?;
+    let block = rpc.get_block(&block_hash)?;
+    let confirmed = block.txdata.iter().any(|tx| tx.compute_txid() == replacement_txid);
+    say!("📦 Block {} mined!", block_hash);
+    say!("   └─ Replacement (payment #{} cancelled) confirmed: {}", drop_index, if confirmed { "✅ YES" } else { "❌ NO" });
+
+    Ok(())
+}
+
+pub async fn run_cancel_demo(opts: &Options) -> Result<()> {
+    say!("🚀 RBF Demo - Cancelling a payment (send it back to yourself)\n");
+    let mut story = Story::new(vec![
+        DemoStep::step(1, "Broadcasting a payment that signals RBF"),
+        DemoStep::pause(&["The payment is unconfirmed and signals RBF: it can still be called off"], &["Press Enter to CANCEL it..."]),
+        DemoStep::step(2, "Replacing the payment with a send-to-self"),
+    ], opts.interactive);
+
+    let node = Node::connect(opts)?;
+    let rpc = &node.rpc;
+
+    // Get addresses
+    let funding_addr = rpc.get_new_address(None, None)?.assume_checked();
+    let merchant_addr = rpc.get_new_address(None, None)?.assume_checked();
+    let refund_addr = rpc.get_new_address(None, None)?.assume_checked();
+
+    // Fund wallet if needed
+    node.fund_if_needed(&funding_addr)?;
+    node.confirm_mainnet_fees(&[])?;
+
+    // === STEP 1: A wallet-funded payment that signals RBF ===
+    let payment_amount = Amount::from_sat(50_000_000);
+    let outputs = HashMap::from([(merchant_addr.to_string(), payment_amount)]);
+    let raw = rpc.create_raw_transaction_hex(&[], &outputs, node.raw_lock_time()?, Some(true))?;
+    let options = FundRawTransactionOptions { replaceable: Some(true), ..Default::default() };
+    let funded = rpc.fund_raw_transaction(raw, Some(&options), None)?;
+    let signed = rpc.sign_raw_transaction_with_wallet(&funded.hex, None, None)?;
+    let payment_txid = node.broadcast(&signed.hex, None)?;
+    story.advance()?;
+    say!("   ├─ Payment: {}", payment_txid);
+    say!("   ├─ Pays: {} to {}", btc(payment_amount), merchant_addr);
+    say!("   └─ Fee: {} (RBF: ENABLED)\n", btc(funded.fee));
+
+    // === STEP 2: Double-spend its inputs back to ourselves ===
+    story.advance()?;
+    let new_fee = node.min_replacement_fee(payment_txid)? + REPLACEMENT_MARGIN;
+    let before = rpc.get_raw_mempool()?;
+    let cancel_txid = node.rbf_cancel(payment_txid, &refund_addr, new_fee)?;
+    say!("✅ Cancellation TX broadcasted: {}", cancel_txid);
+
+    say!("\n🔍 Mempool Status (After RBF):");
+    say!("{}", mempool_delta(&before, &rpc.get_raw_mempool()?));
+
+    let block_hash = node.confirm(node.confirmer(&funding_addr), &[cancel_txid]).await?;
+    let block = rpc.get_block(&block_hash)?;
+    let cancelled = block.txdata.iter().any(|tx| tx.compute_txid() == cancel_txid)
+        && !block.txdata.iter().any(|tx| tx.compute_txid() == payment_txid);
+    say!("📦 Block {} mined!", block_hash);
+    if cancelled {
+        say!("   └─ 🚫 Payment CANCELLED: the merchant never received {}, the coins are back in the wallet", btc(payment_amount));
+    } else {
+        say!("   └─ ❌ The cancellation did not confirm: check the mempool");
+    }
+
+    Ok(())
+}
+
+pub async fn run_add_recipient_demo(opts: &Options) -> Result<()> {
+    say!("🚀 RBF Demo - Adding a forgotten recipient to a payment\n");
+    let mut story = Story::new(vec![
+        DemoStep::step(1, "Broadcasting a payment with change that signals RBF"),
+        DemoStep::pause(
+            &["Oops: a second person needed paying too, and the payment is still unconfirmed"],
+            &["Press Enter to ADD the forgotten recipient..."],
+        ),
+        DemoStep::step(2, "Replacing the payment with one that also pays the forgotten recipient"),
+    ], opts.interactive);
+
+    let node = Node::connect(opts)?;
+    let rpc = &node.rpc;
+
+    // Get addresses
+    let funding_addr = rpc.get_new_address(None, None)?.assume_checked();
+    let merchant_addr = rpc.get_new_address(None, None)?.assume_checked();
+    let forgotten_addr = rpc.get_new_address(None, None)?.assume_checked();
+
+    // Fund wallet if needed
+    node.fund_if_needed(&funding_addr)?;
+    let fee = Amount::from_sat(1_000);
+    node.confirm_mainnet_fees(&[("Payment fee", fee)])?;
+
+    // === STEP 1: A payment with change that signals RBF ===
+    let utxo = node.spendable_utxo(Amount::ONE_BTC)?;
+    node.lock_utxo(OutPoint::new(utxo.txid, utxo.vout))?;
+    let payment = Amount::from_btc(0.5)?;
+    let tx = node.tx_builder()?
+        .input(OutPoint::new(utxo.txid, utxo.vout), SEQUENCE_RBF)
+        .pay(payment, &merchant_addr)
+        .pay(utxo.amount - payment - fee, node.change_address()?)
+        .build();
+    let payment_txid = node.broadcast(&node.sign_with_wallet(&tx)?.hex, None)?;
+    story.advance()?;
+    say!("   ├─ Payment: {}", payment_txid);
+    say!("   ├─ Pays: {} to {}", btc(payment), merchant_addr);
+    say!("   └─ Fee: {} (RBF: ENABLED)\n", btc(fee));
+
+    // === STEP 2: Replace it with a version that pays the second person too ===
+    let forgotten = Amount::from_btc(0.25)?;
+    let new_fee = node.min_replacement_fee(payment_txid)? + REPLACEMENT_MARGIN;
+    node.confirm_mainnet_fees(&[("Replacement fee", new_fee)])?;
+    story.advance()?;
+    say!("   └─ Also pays: {} to {}", btc(forgotten), forgotten_addr);
+    let before = rpc.get_raw_mempool()?;
+    let replacement_txid = node.rbf_add_recipient(payment_txid, &forgotten_addr, forgotten, new_fee)?;
+    say!("✅ Replacement TX broadcasted: {}", replacement_txid);
+    node.verify_fee(replacement_txid, new_fee)?;
+
+    say!("\n🔍 Mempool Status (After RBF):");
+    say!("{}", mempool_delta(&before, &rpc.get_raw_mempool()?));
+
+    let block_hash = node.confirm(node.confirmer(&funding_addr), &[replacement_txid]).await?;
+    let block = rpc.get_block(&block_hash)?;
+    let confirmed = block.txdata.iter().any(|tx| tx.compute_txid() == replacement_txid);
+    say!("📦 Block {} mined!", block_hash);
+    say!("   └─ Both recipients paid in one transaction: {}", if confirmed { "✅ YES" } else { "❌ NO" });
+
+    Ok(())
+}
+
+pub async fn run_rejection_demo(opts: &Options) -> Result<()> {
+    say!("🚀 RBF Demo - Replacing a transaction that doesn't signal RBF\n");
+
+    let node = Node::connect(opts)?;
+    let funding_addr = node.rpc.get_new_address(None, None)?.assume_checked();
+    node.fund_if_needed(&funding_addr)?;
+    node.confirm_mainnet_fees(&[("Payment fee", Amount::from_sat(1_000)), ("Replacement fee", REPLACEMENT_MARGIN * 2)])?;
+
+    let demo = node.demonstrate_rbf_rejection()?;
+    match (&demo.replacement_txid, &demo.rejection) {
+        (Some(replacement), _) => {
+            say!("✅ Replacement {} ACCEPTED despite no signal", replacement);
+            say!("   └─ 💡 This node runs full RBF: it replaces any unconfirmed transaction that pays more.");
+            say!("      An opt-in-only node (Core < 28, or mempoolfullrbf=0) would refuse it with txn-mempool-conflict.");
+        }
+        (None, Some(reason)) => {
+            say!("❌ Replacement REJECTED: {}", reason);
+            say!("   └─ 💡 Without a BIP125 signal (an input sequence below 0xfffffffe) this node never replaces.");
+            say!("      A full-RBF node (mempoolfullrbf=1, the default since Core 28) would have taken it.");
+        }
+        (None, None) => {}
+    }
+    say!("\n🧾 {}", demo);
+
+    // Clear whichever one won out of the mempool
+    let winner = demo.replacement_txid.unwrap_or(demo.original_txid);
+    let block_hash = node.confirm(node.confirmer(&funding_addr), &[winner]).await?;
+    say!("📦 Block {} mined: {} confirmed", block_hash, winner);
+
+    Ok(())
+}
+
+pub async fn run_cluster_demo(opts: &Options) -> Result<()> {
+    say!("🚀 RBF Demo - Replacing a transaction with unconfirmed parents\n");
+    let mut story = Story::new(vec![
+        DemoStep::step(1, "Building a cluster: 2 low-fee parents, 1 payment spending both"),
+        DemoStep::step(2, "Replacing the payment so the whole package gets more attractive"),
+    ], opts.interactive);
+
+    let node = Node::connect(opts)?;
+    let rpc = &node.rpc;
+
+    // Get addresses
+    let funding_addr = rpc.get_new_address(None, None)?.assume_checked();
+    let target_addr = rpc.get_new_address(None, None)?.assume_checked();
+
+    // Fund wallet if needed
+    node.fund_if_needed(&funding_addr)?;
+    let low_fee = Amount::from_sat(1_000);
+    node.confirm_mainnet_fees(&[("Parent fees", low_fee * 2), ("Payment fee", low_fee)])?;
+
+    // === STEP 1: Two stuck parents and a payment spending both ===
+    story.advance()?;
+    let original = node.broadcast_two_parent_cluster(low_fee, low_fee, &target_addr)?;
+
+    // === STEP 2: Replace the payment so the whole package gets more attractive ===
+    let target = fees::target_feerate(&*node.fee_estimator()).unwrap_or(FALLBACK_TARGET_FEERATE);
+    let entry = rpc.get_mempool_entry(&original)?;
+    let ancestor_vsize = (entry.ancestor_size - entry.vsize) as usize;
+    let needed = sim::cpfp_child_fee(ancestor_vsize, entry.fees.ancestor - entry.fees.base, entry.vsize as usize, target);
+    let new_fee = needed.max(node.min_replacement_fee(original)? + REPLACEMENT_MARGIN);
+    node.confirm_mainnet_fees(&[("Replacement fee", new_fee)])?;
+    story.advance()?;
+    say!("   └─ Fee: {} (package target {})", btc(new_fee), feerate(target));
+    let before = rpc.get_raw_mempool()?;
+    let replacement = node.replace_in_cluster(original, new_fee)?;
+    say!("✅ Replacement TX broadcasted: {}", replacement);
+    say!("{}", mempool_delta(&before, &rpc.get_raw_mempool()?));
+    say!("💡 The parents stay: only the payment was replaced, and its higher fee pulls them along");
+
+    let block_hash = node.confirm(node.confirmer(&funding_addr), &[replacement]).await?;
+    let block = rpc.get_block(&block_hash)?;
+    let confirmed = block.txdata.iter().any(|tx| tx.compute_txid() == replacement);
+    say!("\n📦 Block {} mined!", block_hash);
+    say!("   └─ Replacement and its parents confirmed: {}", if confirmed { "✅ YES" } else { "❌ NO" });
+
+    Ok(())
+}
+
+pub async fn run_parent_child_demo(opts: &Options) -> Result<()> {
+    say!("🚀 RBF Demo - Replacing a CPFP parent without losing its child\n");
+    let mut story = Story::new(vec![
+        DemoStep::step(1, "Broadcasting a low-fee parent that signals RBF"),
+        DemoStep::step(2, "Spending the parent's payment output in a child"),
+        DemoStep::pause(&["A plain RBF of the parent would evict the child with it"], &["Press Enter to replace the parent AND carry the child over..."]),
+        DemoStep::step(3, "Replacing the parent and rebuilding the child on top"),
+    ], opts.interactive);
+
+    let node = Node::connect(opts)?;
+    let rpc = &node.rpc;
+
+    // Get addresses
+    let funding_addr = rpc.get_new_address(None, None)?.assume_checked();
+    let merchant_addr = rpc.get_new_address(None, None)?.assume_checked();
+    let target_addr = rpc.get_new_address(None, None)?.assume_checked();
+
+    // Fund wallet if needed
+    node.fund_if_needed(&funding_addr)?;
+    let low_fee = Amount::from_sat(1_000);
+    node.confirm_mainnet_fees(&[("Parent fee", low_fee), ("Child fee", low_fee)])?;
+
+    // === STEP 1: A low-fee parent that signals RBF ===
+    let utxo = node.spendable_utxo(Amount::ONE_BTC)?;
+    node.lock_utxo(OutPoint::new(utxo.txid, utxo.vout))?;
+    let payment = Amount::from_btc(0.5)?;
+    let parent = node.tx_builder()?
+        .input(OutPoint::new(utxo.txid, utxo.vout), SEQUENCE_RBF)
+        .pay(payment, &merchant_addr)
+        .pay(utxo.amount - payment - low_fee, node.change_address()?)
+        .build();
+    let parent_txid = node.broadcast(&node.sign_with_wallet(&parent)?.hex, None)?;
+    story.advance()?;
+    say!("   └─ Parent: {} (fee: {}, RBF: ENABLED)", parent_txid, btc(low_fee));
+
+    // === STEP 2: Someone already spent its payment output ===
+    let child_txid = node.cpfp_child(OutPoint::new(parent_txid, 0), low_fee, &target_addr)?;
+    story.advance()?;
+    say!("   └─ Child: {} (fee: {})", child_txid, btc(low_fee));
+
+    // === STEP 3: Replace the parent and rebuild the child on top ===
+    let new_fee = node.min_replacement_fee(parent_txid)? + node.mempool_fee(child_txid)? + REPLACEMENT_MARGIN;
+    node.confirm_mainnet_fees(&[("Replacement parent fee", new_fee)])?;
+    story.advance()?;
+    say!("   └─ New parent fee: {}", btc(new_fee));
+    let before = rpc.get_raw_mempool()?;
+    let (new_parent, new_child) = node.rbf_parent_preserving_child(parent_txid, child_txid, new_fee)?;
+    say!("{}", mempool_delta(&before, &rpc.get_raw_mempool()?));
+
+    let block_hash = node.confirm(node.confirmer(&funding_addr), &[new_child]).await?;
+    let block = rpc.get_block(&block_hash)?;
+    let mined = |txid: Txid| block.txdata.iter().any(|tx| tx.compute_txid() == txid);
+    say!("\n📦 Block {} mined!", block_hash);
+    say!("   ├─ Replacement parent confirmed: {}", if mined(new_parent) { "✅ YES" } else { "❌ NO" });
+    say!("   └─ Rebuilt child confirmed: {}", if mined(new_child) { "✅ YES" } else { "❌ NO" });
+
+    Ok(())
+}
+
+pub async fn run_conflict_demo(opts: &Options) -> Result<()> {
+    say!("🚀 RBF Demo - Winning back a UTXO someone else double-spent\n");
+    let mut story = Story::new(vec![
+        DemoStep::step(1, "Broadcasting our payment"),
+        DemoStep::step(2, "A double-spend of the same UTXO evicts it and gets a child"),
+        DemoStep::pause(&["Replacing just the double-spend is not enough: its child has to go too"], &["Press Enter to evict the whole conflict set..."]),
+        DemoStep::step(3, "Paying the merchant again, outbidding the whole conflict set"),
+    ], opts.interactive);
+
+    let node = Node::connect(opts)?;
+    let rpc = &node.rpc;
+
+    // Get addresses
+    let funding_addr = rpc.get_new_address(None, None)?.assume_checked();
+    let merchant_addr = rpc.get_new_address(None, None)?.assume_checked();
+    let other_addr = rpc.get_new_address(None, None)?.assume_checked();
+
+    // Fund wallet if needed
+    node.fund_if_needed(&funding_addr)?;
+    let low_fee = Amount::from_sat(1_000);
+    node.confirm_mainnet_fees(&[("Payment fee", low_fee)])?;
+
+    // === STEP 1: Our payment ===
+    let utxo = node.spendable_utxo(Amount::ONE_BTC)?;
+    let outpoint = OutPoint::new(utxo.txid, utxo.vout);
+    node.lock_utxo(outpoint)?;
+    let payment = node.tx_builder()?
+        .input(outpoint, SEQUENCE_RBF)
+        .pay(utxo.amount - low_fee, &merchant_addr)
+        .build();
+    let payment_txid = node.broadcast(&node.sign_with_wallet(&payment)?.hex, None)?;
+    story.advance()?;
+    say!("   └─ Payment: {} (fee: {})", payment_txid, btc(low_fee));
+
+    // === STEP 2: A double-spend of the same UTXO replaces it and gets a child ===
+    // Stands in for a second device holding the same keys
+    let double_fee = node.min_replacement_fee(payment_txid)? + REPLACEMENT_MARGIN;
+    let double_spend = node.tx_builder()?
+        .input(outpoint, SEQUENCE_RBF)
+        .pay(utxo.amount - double_fee, &other_addr)
+        .build();
+    let double_txid = node.broadcast(&node.sign_with_wallet(&double_spend)?.hex, None)?;
+    let child_txid = node.cpfp_child(OutPoint::new(double_txid, 0), low_fee, &other_addr)?;
+    story.advance()?;
+    say!("   ├─ Double-spend: {} (fee: {})", double_txid, btc(double_fee));
+    say!("   └─ Child: {}", child_txid);
+
+    // === STEP 3: Outbid the whole conflict set ===
+    let fee = double_fee + node.min_replacement_fee(child_txid)? + REPLACEMENT_MARGIN * 2;
+    node.confirm_mainnet_fees(&[("Resolving fee", fee)])?;
+    story.advance()?;
+    say!("   └─ Fee: {}", btc(fee));
+    let resolved = node.resolve_conflict(outpoint, &merchant_addr, fee)?;
+    say!("✅ Resolving TX broadcasted: {}", resolved);
+
+    let block_hash = node.confirm(node.confirmer(&funding_addr), &[resolved]).await?;
+    let block = rpc.get_block(&block_hash)?;
+    let confirmed = block.txdata.iter().any(|tx| tx.compute_txid() == resolved);
+    say!("\n📦 Block {} mined!", block_hash);
+    say!("   └─ Merchant paid: {}", if confirmed { "✅ YES" } else { "❌ NO" });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::absolute::LockTime;
+    use bitcoin::hashes::Hash;
+    use bitcoin::transaction::Version;
+
+    use crate::builder::TxBuilder;
+    use crate::consts::DEFAULT_INCREMENTAL_RELAY_FEE;
+
+    fn spend(outpoints: &[OutPoint]) -> Transaction {
+        outpoints.iter().fold(TxBuilder::new(Version::TWO, LockTime::ZERO), |tx, outpoint| tx.input(*outpoint, SEQUENCE_FINAL)).build()
+    }
+
+    /// A replacement may add inputs but never drop one of the original's.
+    #[test]
+    fn same_inputs() {
+        let (a, b) = (OutPoint::new(Txid::all_zeros(), 0), OutPoint::new(Txid::all_zeros(), 1));
+        assert!(assert_same_inputs(&spend(&[a]), &spend(&[a])).is_ok());
+        assert!(assert_same_inputs(&spend(&[a]), &spend(&[b, a])).is_ok());
+        assert!(matches!(
+            assert_same_inputs(&spend(&[a, b]), &spend(&[a])),
+            Err(RbfError::InputsDropped { missing }) if missing == [b]
+        ));
+    }
+
+    /// Rule 3 refuses a lower absolute fee; rule 4 refuses a higher one that
+    /// doesn't also pay the incremental relay fee for the replacement's vsize.
+    #[test]
+    fn replacement_fee_rules() {
+        let incremental = DEFAULT_INCREMENTAL_RELAY_FEE; // 1 sat/vB
+        let original = Amount::from_sat(1_000);
+        assert!(matches!(
+            check_replacement_fee(original, 200, Amount::from_sat(999), 200, incremental),
+            Err(RbfError::FeeNotIncreased { .. })
+        ));
+        assert!(matches!(
+            check_replacement_fee(original, 200, Amount::from_sat(1_150), 250, incremental),
+            Err(RbfError::InsufficientForBandwidth { required, added_vbytes: 50, .. }) if required == Amount::from_sat(1_250)
+        ));
+        assert!(check_replacement_fee(original, 200, Amount::from_sat(1_250), 250, incremental).is_ok());
+    }
+}
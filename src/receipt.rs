@@ -0,0 +1,111 @@
+//! `--receipt <file>`: a durable, parseable record of one acceleration, for
+//! accounting or downstream reporting. Without the flag the RBF, CPFP and P2A
+//! demos print it as JSON at the end instead.
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::cpfp::CpfpOutcome;
+use crate::deadline::Method;
+use crate::fmt::{btc, feerate};
+use crate::options::Options;
+use crate::p2a::P2aOutcome;
+use crate::rbf::RbfOutcome;
+
+/// How one of the demos ended.
+pub enum Outcome {
+    Rbf(RbfOutcome),
+    Cpfp(CpfpOutcome),
+    P2a(P2aOutcome),
+}
+
+/// What was accelerated, how, at what cost, and where it confirmed.
+#[derive(Serialize)]
+pub struct Receipt {
+    pub method: String,
+    /// The stuck transaction.
+    pub original_txid: String,
+    /// The replacement (RBF) or the child that paid for it (CPFP, P2A).
+    pub accelerating_txid: String,
+    pub original_fee_sat: u64,
+    pub accelerating_fee_sat: u64,
+    /// Fees that end up in a block: the replacement's alone for RBF (the
+    /// original is never mined), parent and child together otherwise.
+    pub total_fee_paid_sat: u64,
+    /// The three fees above as printed (`--unit`).
+    pub original_fee: String,
+    pub accelerating_fee: String,
+    pub total_fee_paid: String,
+    pub original_feerate_sat_vb: f64,
+    /// The replacement's feerate, or the package's.
+    pub effective_feerate_sat_vb: f64,
+    pub confirmed: bool,
+    /// Where the accelerating transaction confirmed, `null` if it didn't.
+    pub block_hash: Option<String>,
+    pub block_height: Option<u64>,
+    pub summary: String,
+}
+
+pub fn receipt(outcome: &Outcome) -> Receipt {
+    let (method, original, accelerating, original_fee, accelerating_fee, original_rate, effective_rate, confirmed, block) = match outcome {
+        Outcome::Rbf(o) => (
+            Method::Rbf, o.original_txid, o.replacement_txid, o.original_fee, o.replacement_fee,
+            o.original_feerate, o.replacement_feerate, o.replacement_confirmed, o.block,
+        ),
+        Outcome::Cpfp(o) => (
+            Method::Cpfp, o.parent_txid, o.child_txid, o.parent_fee, o.child_fee,
+            o.parent_feerate, o.package_feerate, o.parent_confirmed && o.child_confirmed, o.block,
+        ),
+        Outcome::P2a(o) => (
+            Method::P2a, o.parent_txid, o.anchor_spend_txid, o.parent_fee, o.anchor_spend_fee,
+            o.parent_feerate, o.package_feerate, o.parent_confirmed && o.anchor_spend_confirmed, o.block,
+        ),
+    };
+    let total_paid = match method {
+        Method::Rbf => accelerating_fee,
+        Method::Cpfp | Method::P2a => original_fee + accelerating_fee,
+    };
+    let block = confirmed.then_some(block);
+    let summary = match block {
+        Some((hash, height)) => format!(
+            "{} accelerated {} from {} to {} with {}, paying {} in total; confirmed in block {} ({})",
+            method, original, feerate(original_rate), feerate(effective_rate), accelerating, btc(total_paid), height, hash
+        ),
+        None => format!(
+            "{} tried to accelerate {} from {} to {} with {} ({} in total), but it did not confirm",
+            method, original, feerate(original_rate), feerate(effective_rate), accelerating, btc(total_paid)
+        ),
+    };
+    Receipt {
+        method: method.to_string(),
+        original_txid: original.to_string(),
+        accelerating_txid: accelerating.to_string(),
+        original_fee_sat: original_fee.to_sat(),
+        accelerating_fee_sat: accelerating_fee.to_sat(),
+        total_fee_paid_sat: total_paid.to_sat(),
+        original_fee: btc(original_fee),
+        accelerating_fee: btc(accelerating_fee),
+        total_fee_paid: btc(total_paid),
+        original_feerate_sat_vb: original_rate,
+        effective_feerate_sat_vb: effective_rate,
+        confirmed,
+        block_hash: block.map(|(hash, _)| hash.to_string()),
+        block_height: block.map(|(_, height)| height),
+        summary,
+    }
+}
+
+/// Writes `outcome`'s receipt to `--receipt`, or prints it if none was given.
+pub fn emit(opts: &Options, outcome: &Outcome) -> Result<()> {
+    let receipt = receipt(outcome);
+    let json = serde_json::to_string_pretty(&receipt)?;
+    match &opts.receipt {
+        Some(path) => {
+            std::fs::write(path, json + "\n")?;
+            say!("📄 {}", receipt.summary);
+            say!("   └─ Receipt written to {}", path.display());
+        }
+        None => say!("📄 Receipt:\n{}", json),
+    }
+    Ok(())
+}
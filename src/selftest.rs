@@ -0,0 +1,143 @@
+//! End-to-end check of the RBF, CPFP (also with a replaceable child) and P2A
+//! flows (the latter also with a P2SH-wrapped segwit fee input, with
+//! BIP69-sorted outputs, with wallet-funded fee inputs and with the anchor
+//! spend replaced by RBF) against a live regtest node.
+//!
+//! Build with `--features regtest-tests` and run with `-- --self-test`. The node
+//! is taken from `BITCOIND_RPC_URL`, `BITCOIND_RPC_USER` and `BITCOIND_RPC_PASS`.
+
+use anyhow::{ensure, Context, Result};
+use bitcoin::Amount;
+use bitcoincore_rpc::json::ListUnspentResultEntry;
+use bitcoincore_rpc::{Client, RpcApi};
+use serde_json::json;
+
+use crate::node::Node;
+use crate::options::Options;
+use crate::receipt::{self, Outcome};
+use crate::{cpfp, p2a, rbf};
+
+/// Wallet every self-test run uses, created on the first run and reloaded after.
+const SELFTEST_WALLET: &str = "selftest";
+
+pub async fn run() -> Result<()> {
+    let defaults = Options::default();
+    let opts = Options {
+        rpc_url: std::env::var("BITCOIND_RPC_URL").unwrap_or(defaults.rpc_url.clone()),
+        rpc_user: std::env::var("BITCOIND_RPC_USER").unwrap_or(defaults.rpc_user.clone()),
+        rpc_pass: std::env::var("BITCOIND_RPC_PASS").unwrap_or(defaults.rpc_pass.clone()),
+        // One fixed name, so runs reuse a single wallet instead of leaving one behind each
+        wallet: SELFTEST_WALLET.to_string(),
+        interactive: false,
+        ..defaults
+    };
+
+    let result = run_flows(&opts).await;
+
+    // Always unload the wallet, even when a flow failed, without masking that failure
+    match Client::new(&opts.rpc_url, Node::auth(&opts)?).and_then(|rpc| rpc.unload_wallet(Some(&opts.wallet))) {
+        Ok(_) => say!("\n🧹 Unloaded wallet {}", opts.wallet),
+        Err(e) => say!("\n⚠️  Could not unload wallet {}: {}", opts.wallet, e),
+    }
+
+    result?;
+    say!("✅ Self-test passed: RBF, CPFP and P2A all behaved as expected");
+    Ok(())
+}
+
+async fn run_flows(opts: &Options) -> Result<()> {
+    // Mature plenty of coinbases up front so every flow has spare UTXOs
+    let node = Node::connect(opts)?;
+    let addr = node.rpc.get_new_address(None, None)?.assume_checked();
+    node.rpc.generate_to_address(110, &addr)?;
+    check_immature_coinbase_skipped(&node)?;
+    check_cluster_replacement(&node)?;
+
+    let rbf = rbf::run_demo(opts).await?.context("RBF flow did not run")?;
+    ensure!(rbf.original_evicted, "RBF: original {} was not evicted", rbf.original_txid);
+    ensure!(rbf.replacement_confirmed && !rbf.original_confirmed, "unexpected RBF result: {}", rbf);
+    let fees = (rbf.original_fee, rbf.replacement_fee);
+    let rbf_receipt = receipt::receipt(&Outcome::Rbf(rbf));
+    ensure!(
+        rbf_receipt.confirmed && rbf_receipt.block_height.is_some() && rbf_receipt.total_fee_paid_sat == fees.1.to_sat(),
+        "RBF receipt should charge only the replacement's {} (original {}): {}", fees.1, fees.0, rbf_receipt.summary
+    );
+
+    let cpfp = cpfp::run_demo(opts).await?.context("CPFP flow did not run")?;
+    ensure!(cpfp.parent_confirmed && cpfp.child_confirmed, "CPFP package not confirmed: {}", cpfp);
+    ensure!(cpfp.beat_competition, "CPFP package did not win the limited block: {}", cpfp);
+
+    // Same flow with an RBF-signalling child that gets replaced before mining
+    let replaceable_opts = Options { replaceable_child: true, ..opts.clone() };
+    let cpfp = cpfp::run_demo(&replaceable_opts).await?.context("CPFP flow (replaceable child) did not run")?;
+    ensure!(cpfp.parent_confirmed && cpfp.child_confirmed, "CPFP package with replaced child not confirmed: {}", cpfp);
+
+    let p2a = p2a::run_demo(opts).await?.context("P2A flow did not run")?;
+    ensure!(p2a.parent_confirmed && p2a.anchor_spend_confirmed, "P2A package not confirmed: {}", p2a);
+
+    // Same flow with a wrapped-segwit fee input (redeemScript + witness)
+    let p2sh_opts = Options { p2sh_fee_input: true, ..opts.clone() };
+    let p2a = p2a::run_demo(&p2sh_opts).await?.context("P2A flow (P2SH-segwit fee input) did not run")?;
+    ensure!(p2a.parent_confirmed && p2a.anchor_spend_confirmed, "P2A package with P2SH-segwit fee input not confirmed: {}", p2a);
+
+    // Same flow with BIP69-sorted outputs, which moves the anchor to vout 0
+    let sorted_opts = Options { sort_outputs: true, ..opts.clone() };
+    let p2a = p2a::run_demo(&sorted_opts).await?.context("P2A flow (BIP69-sorted outputs) did not run")?;
+    ensure!(p2a.parent_confirmed && p2a.anchor_spend_confirmed, "P2A package with sorted outputs not confirmed: {}", p2a);
+
+    // Same flow with the wallet adding the fee inputs and change (fundrawtransaction)
+    let funded_opts = Options { fund_anchor_spend: true, ..opts.clone() };
+    let p2a = p2a::run_demo(&funded_opts).await?.context("P2A flow (wallet-funded anchor spend) did not run")?;
+    ensure!(p2a.parent_confirmed && p2a.anchor_spend_confirmed, "P2A package with wallet-funded anchor spend not confirmed: {}", p2a);
+
+    // Same flow with the anchor spend itself replaced by a higher-fee one
+    let bump_opts = Options { bump_anchor: true, ..opts.clone() };
+    let p2a = p2a::run_demo(&bump_opts).await?.context("P2A flow (RBF'd anchor spend) did not run")?;
+    ensure!(p2a.parent_confirmed && p2a.anchor_spend_confirmed, "P2A package with RBF'd anchor spend not confirmed: {}", p2a);
+
+    Ok(())
+}
+
+/// A fresh coinbase paying the wallet must never be picked as a UTXO to spend.
+fn check_immature_coinbase_skipped(node: &Node) -> Result<()> {
+    let addr = node.rpc.get_new_address(None, None)?.assume_checked();
+    let block = node.rpc.generate_to_address(1, &addr)?[0];
+    let coinbase = node.rpc.get_block(&block)?.txdata[0].compute_txid();
+    // Core ≥ 25 lists immature coinbases on request; older nodes never do
+    let listed: Vec<ListUnspentResultEntry> = node.rpc.call(
+        "listunspent",
+        &[json!(1), json!(9_999_999), json!([addr.to_string()]), json!(true), json!({ "include_immature_coinbase": true })],
+    )?;
+    if let Some(entry) = listed.iter().find(|u| u.txid == coinbase) {
+        ensure!(!node.is_mature(entry)?, "coinbase {} with {} confirmation(s) counted as mature", coinbase, entry.confirmations);
+    }
+    ensure!(
+        !node.spendable_utxos(Amount::ZERO)?.iter().any(|u| u.txid == coinbase),
+        "immature coinbase {} offered as a spendable UTXO", coinbase
+    );
+    say!("✅ Immature coinbase {} skipped by UTXO selection", coinbase);
+    Ok(())
+}
+
+/// Replacing a payment that has two unconfirmed parents must be refused when
+/// the ancestor package's feerate doesn't rise, and confirm with its parents
+/// when it does.
+fn check_cluster_replacement(node: &Node) -> Result<()> {
+    let recipient = node.rpc.get_new_address(None, None)?.assume_checked();
+    let fee = Amount::from_sat(1_000);
+    let original = node.broadcast_two_parent_cluster(fee, fee, &recipient)?;
+    let entry = node.rpc.get_mempool_entry(&original)?;
+    ensure!(entry.ancestor_count == 3, "cluster: expected 2 ancestors, got {}", entry.ancestor_count - 1);
+
+    let refused = node.replace_in_cluster(original, fee);
+    ensure!(
+        matches!(refused.as_ref().map_err(|e| e.downcast_ref()), Err(Some(rbf::RbfError::ClusterNotImproved { .. }))),
+        "cluster: a replacement that doesn't improve the package was not refused"
+    );
+    let replacement = node.replace_in_cluster(original, fee * 20)?;
+    let block = node.rpc.generate_to_address(1, &recipient)?[0];
+    let mined: Vec<_> = node.rpc.get_block(&block)?.txdata.iter().map(|tx| tx.compute_txid()).collect();
+    ensure!(mined.contains(&replacement) && !mined.contains(&original), "cluster: replacement {} not mined", replacement);
+    say!("✅ Cluster RBF: non-improving replacement refused, improving one mined with its 2 parents\n");
+    Ok(())
+}
@@ -0,0 +1,264 @@
+//! "What-if" fee arithmetic for planning an acceleration. Nothing in here talks
+//! to a node: every function takes plain sizes (vbytes), fees and feerates
+//! (sat/vB), or already-built transactions.
+
+use anyhow::{anyhow, Result};
+use bitcoin::constants::WITNESS_SCALE_FACTOR;
+use bitcoin::{Amount, ScriptBuf, Transaction};
+use std::fmt as stdfmt;
+
+use crate::consts::{DEFAULT_INCREMENTAL_RELAY_FEE, P2WPKH_DUST_SAT};
+use crate::fmt;
+
+/// Fee for `weight` weight units at `sat_per_vb`, rounded the way bitcoind's
+/// `CFeeRate::GetFee` does: vsize is `weight / 4` rounded up, the feerate is
+/// taken in whole sat/kvB and the fee rounds up to the next satoshi.
+pub fn fee_from_feerate(weight: usize, sat_per_vb: f64) -> Amount {
+    let vsize = weight.div_ceil(WITNESS_SCALE_FACTOR) as u64;
+    let sat_per_kvb = (sat_per_vb * 1000.0).round() as u64;
+    Amount::from_sat((sat_per_kvb * vsize).div_ceil(1000))
+}
+
+/// Feerate in sat/vB of paying `fee` for `vsize` vbytes.
+pub fn feerate(fee: Amount, vsize: usize) -> f64 {
+    fee.to_sat() as f64 / vsize as f64
+}
+
+/// How much higher `replacement` is than `original`, in percent (both sat/vB).
+pub fn feerate_improvement(original: f64, replacement: f64) -> f64 {
+    (replacement - original) / original * 100.0
+}
+
+/// Feerate a miner sees for parent and child taken together.
+pub fn package_feerate(parent_fee: Amount, parent_vsize: usize, child_fee: Amount, child_vsize: usize) -> f64 {
+    feerate(parent_fee + child_fee, parent_vsize + child_vsize)
+}
+
+/// A parent and child as a miner selecting by ancestor feerate sees them
+/// (all sat/vB). The child can't be mined without the parent, so it is ranked
+/// at the package feerate; the parent alone only at its own.
+pub struct MinerView {
+    pub package_feerate: f64,
+    pub parent_alone_feerate: f64,
+    pub child_alone_feerate: f64,
+}
+
+impl MinerView {
+    /// Whether the package wins a block slot over a transaction at `marginal`
+    /// sat/vB, the last one that would otherwise still fit.
+    pub fn beats(&self, marginal: f64) -> bool {
+        self.package_feerate > marginal
+    }
+}
+
+/// The three feerates a miner compares for a parent and its child.
+pub fn miner_view(parent_fee: Amount, parent_vsize: usize, child_fee: Amount, child_vsize: usize) -> MinerView {
+    MinerView {
+        package_feerate: package_feerate(parent_fee, parent_vsize, child_fee, child_vsize),
+        parent_alone_feerate: feerate(parent_fee, parent_vsize),
+        child_alone_feerate: feerate(child_fee, child_vsize),
+    }
+}
+
+/// The sizes of one transaction. Fees and BIP125 rules are all in vbytes
+/// (weight / 4): witness bytes count a quarter, so `vsize` sits between the
+/// stripped size and the raw size.
+pub struct SizeBreakdown {
+    pub weight: u64,
+    pub vsize: usize,
+    /// Serialized size including the witness.
+    pub total_size: usize,
+    /// Serialized size without the witness.
+    pub stripped_size: usize,
+}
+
+pub fn size_breakdown(tx: &Transaction) -> SizeBreakdown {
+    SizeBreakdown {
+        weight: tx.weight().to_wu(),
+        vsize: tx.vsize(),
+        total_size: tx.total_size(),
+        stripped_size: tx.base_size(),
+    }
+}
+
+impl stdfmt::Display for SizeBreakdown {
+    fn fmt(&self, f: &mut stdfmt::Formatter<'_>) -> stdfmt::Result {
+        write!(
+            f,
+            "{} B raw ({} B without witness), {} WU = {} vB ({} vB saved by the witness discount)",
+            self.total_size, self.stripped_size, self.weight, self.vsize, self.total_size - self.vsize
+        )
+    }
+}
+
+/// Fee a child of `child_vsize` vbytes must pay so the package reaches
+/// `target_feerate`. Zero if the parent alone already does.
+pub fn cpfp_child_fee(parent_vsize: usize, parent_fee: Amount, child_vsize: usize, target_feerate: f64) -> Amount {
+    let package_fee = fee_from_feerate((parent_vsize + child_vsize) * WITNESS_SCALE_FACTOR, target_feerate);
+    package_fee.checked_sub(parent_fee).unwrap_or(Amount::ZERO)
+}
+
+/// Splits `input` for a CPFP parent paying `parent_feerate` (sized as a
+/// [`TYPICAL_PARENT_VSIZE`] payment plus change): an output the child can
+/// spend, big enough for `child_fee_budget` plus a non-dust P2WPKH output of
+/// its own, and the parent's fee. Returns `(parent_output, parent_fee)`; the
+/// rest of `input` is change, folded into the output when it would be dust.
+/// Fails with the shortfall when `input` can't cover all of that.
+pub fn plan_parent_for_cpfp(input: Amount, parent_feerate: f64, child_fee_budget: Amount) -> Result<(Amount, Amount)> {
+    let dust = Amount::from_sat(P2WPKH_DUST_SAT);
+    let parent_fee = fee_from_feerate(TYPICAL_PARENT_VSIZE * WITNESS_SCALE_FACTOR, parent_feerate);
+    let parent_output = child_fee_budget + dust;
+    let change = input.checked_sub(parent_fee + parent_output).ok_or_else(|| {
+        anyhow!(
+            "{} can't pay a {} parent fee and a {} output for the child ({} short)",
+            fmt::btc(input), fmt::btc(parent_fee), fmt::btc(parent_output), fmt::btc(parent_fee + parent_output - input)
+        )
+    })?;
+    if change < dust {
+        return Ok((parent_output + change, parent_fee));
+    }
+    Ok((parent_output, parent_fee))
+}
+
+/// What `incremental` (per kvB, as `incrementalrelayfee` is given) charges
+/// for `vsize` vbytes, rounded up to the next satoshi like bitcoind.
+pub fn incremental_relay_fee(incremental: Amount, vsize: usize) -> Amount {
+    Amount::from_sat((incremental.to_sat() * vsize as u64).div_ceil(1000))
+}
+
+/// Fee a replacement of `replacement_vsize` vbytes must pay to reach
+/// `target_feerate` while also satisfying BIP125: at least the original's fee
+/// (rule 3) plus the node's `incremental` relay fee for its own size (rule 4).
+pub fn rbf_replacement_fee(original_fee: Amount, replacement_vsize: usize, target_feerate: f64, incremental: Amount) -> Amount {
+    let for_target = fee_from_feerate(replacement_vsize * WITNESS_SCALE_FACTOR, target_feerate);
+    let bip125_min = original_fee + incremental_relay_fee(incremental, replacement_vsize);
+    for_target.max(bip125_min)
+}
+
+/// Largest fee a no-change sweep of `input` to `spk` can pay while its single
+/// output stays above `spk`'s dust limit. Zero if even a free sweep would be dust.
+pub fn max_sweep_fee(input: Amount, spk: &ScriptBuf) -> Amount {
+    input.checked_sub(spk.minimal_non_dust()).unwrap_or(Amount::ZERO)
+}
+
+/// What an RBF actually cost. The fees of a transaction and its replacement
+/// never add up: the evicted original is never mined, so its fee is never paid.
+pub struct RbfCost {
+    /// The replacement's fee: the only fee that ends up in a block.
+    pub paid: Amount,
+    /// The original's fee, had it confirmed.
+    pub would_have_paid: Amount,
+    /// Extra paid for confirming sooner.
+    pub delta: Amount,
+}
+
+pub fn rbf_cost_analysis(original_fee: Amount, replacement_fee: Amount) -> RbfCost {
+    RbfCost {
+        paid: replacement_fee,
+        would_have_paid: original_fee,
+        delta: replacement_fee.checked_sub(original_fee).unwrap_or(Amount::ZERO),
+    }
+}
+
+/// Typical vsize of a 1-input, 1-output P2WPKH child.
+pub const TYPICAL_CHILD_VSIZE: usize = 110;
+
+/// Typical vsize of a 1-input, 2-output P2WPKH parent (payment plus change).
+pub const TYPICAL_PARENT_VSIZE: usize = 141;
+
+fn ask(question: &str) -> Result<String> {
+    say!("{}", question);
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    Ok(input.trim().to_string())
+}
+
+/// Interactive fee planner: works out CPFP and RBF costs for a stuck parent
+/// from numbers alone, without connecting to a node.
+pub fn run_planner() -> Result<()> {
+    let parent_vsize: usize = ask("Parent vsize (vB): ")?.parse()?;
+    let parent_fee = Amount::from_sat(ask("Parent fee (sats): ")?.parse()?);
+    let target: f64 = ask("Target feerate (sat/vB): ")?.parse()?;
+
+    let child_fee = cpfp_child_fee(parent_vsize, parent_fee, TYPICAL_CHILD_VSIZE, target);
+    let replacement_fee = rbf_replacement_fee(parent_fee, parent_vsize, target, DEFAULT_INCREMENTAL_RELAY_FEE);
+
+    say!("\n🔮 Fee plan for {}:", fmt::feerate(target));
+    say!("   ├─ Parent today: {}", fmt::feerate(feerate(parent_fee, parent_vsize)));
+    say!("   ├─ CPFP: child (~{} vB) must pay {}", TYPICAL_CHILD_VSIZE, fmt::sats(child_fee));
+    say!("   │   └─ Package feerate: {}", fmt::feerate(package_feerate(parent_fee, parent_vsize, child_fee, TYPICAL_CHILD_VSIZE)));
+    say!("   └─ RBF: replacement must pay {} in total (at the default incrementalrelayfee)", fmt::sats(replacement_fee));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fees round like bitcoind's: weight up to whole vbytes, then the fee up
+    /// to the next satoshi, including at the vsize boundary.
+    #[test]
+    fn fee_from_feerate_rounds_like_bitcoind() {
+        let cases = [
+            // (weight, sat/vB, expected sats)
+            (564, 1.0, 141),   // exactly 141 vB
+            (561, 1.0, 141),   // 140.25 vB rounds up to 141
+            (565, 1.0, 142),   // 141.25 vB rounds up to 142
+            (564, 2.5, 353),   // 352.5 sats rounds up
+            (564, 0.1, 15),    // 14.1 sats rounds up
+            (400, 1.001, 101), // 100.1 sats rounds up
+            (0, 5.0, 0),
+        ];
+        for (weight, rate, expected) in cases {
+            assert_eq!(fee_from_feerate(weight, rate), Amount::from_sat(expected), "fee_from_feerate({}, {})", weight, rate);
+        }
+    }
+
+    /// The parent's output covers the child's budget plus dust, change too
+    /// small to keep is folded into it, and a short input is refused.
+    #[test]
+    fn parent_plan_leaves_child_its_budget() {
+        let budget = Amount::from_sat(100_000);
+        let (output, fee) = plan_parent_for_cpfp(Amount::ONE_BTC, 10.0, budget).unwrap();
+        assert_eq!((output, fee), (budget + Amount::from_sat(294), Amount::from_sat(1_410)));
+        let tight = budget + Amount::from_sat(294 + 1_410 + 100);
+        let (output, fee) = plan_parent_for_cpfp(tight, 10.0, budget).unwrap();
+        assert_eq!(output + fee, tight);
+        let short = plan_parent_for_cpfp(budget, 10.0, budget).unwrap_err();
+        assert!(short.to_string().contains("short"), "{}", short);
+    }
+
+    #[test]
+    fn package_feerate_is_total_fee_over_total_vsize() {
+        assert_eq!(package_feerate(Amount::from_sat(141), 141, Amount::from_sat(1_959), 110), 2_100.0 / 251.0);
+    }
+
+    /// The child pays the package's fee at the target less what the parent
+    /// already pays, and nothing once the parent alone reaches the target.
+    #[test]
+    fn cpfp_child_fee_covers_parent_deficit() {
+        let child_fee = cpfp_child_fee(141, Amount::from_sat(141), 110, 10.0);
+        assert_eq!(child_fee, Amount::from_sat(2_510 - 141));
+        assert_eq!(package_feerate(Amount::from_sat(141), 141, child_fee, 110), 10.0);
+        assert_eq!(cpfp_child_fee(141, Amount::from_sat(5_000), 110, 10.0), Amount::ZERO);
+    }
+
+    /// The replacement pays the target feerate, or the original's fee plus
+    /// its own bandwidth when that is more.
+    #[test]
+    fn rbf_replacement_fee_meets_target_and_bip125() {
+        assert_eq!(rbf_replacement_fee(Amount::from_sat(141), 141, 10.0, DEFAULT_INCREMENTAL_RELAY_FEE), Amount::from_sat(1_410));
+        assert_eq!(rbf_replacement_fee(Amount::from_sat(1_000), 141, 2.0, DEFAULT_INCREMENTAL_RELAY_FEE), Amount::from_sat(1_141));
+    }
+
+    /// A low-fee parent ranks low alone, its child high alone, and the
+    /// package between them.
+    #[test]
+    fn miner_view_ranks_package_between_parent_and_child() {
+        let view = miner_view(Amount::from_sat(141), 141, Amount::from_sat(2_369), 110);
+        assert_eq!(view.parent_alone_feerate, 1.0);
+        assert_eq!(view.package_feerate, 10.0);
+        assert!(view.child_alone_feerate > view.package_feerate);
+        assert!(view.beats(9.9) && !view.beats(10.0));
+    }
+}